@@ -29,7 +29,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                                 Ok(())
                             })?;
                         }
-                        SessionType::TTY | SessionType::Unspecified => {}
+                        _ => {}
                     }
                     Ok(())
                 })?;
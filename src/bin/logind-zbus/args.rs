@@ -0,0 +1,102 @@
+//! Command line parsing, done by hand as the options are few and shared by all commands
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Table,
+    Json,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Args {
+    pub format: Format,
+    /// Properties given with `-p`, empty for all of them
+    pub properties: Vec<String>,
+    /// Signal sent by `kill-session` and `kill-user`
+    pub signal: i32,
+    /// Processes `kill-session` signals, `leader` or `all`
+    pub kill_whom: String,
+    /// Let polkit ask for a password, off with `--no-ask-password`
+    pub ask_password: bool,
+    pub command: String,
+    pub operands: Vec<String>,
+}
+
+impl Args {
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut parsed = Self {
+            format: Format::Table,
+            properties: Vec::new(),
+            signal: libc::SIGTERM,
+            kill_whom: "all".to_string(),
+            ask_password: true,
+            command: String::new(),
+            operands: Vec::new(),
+        };
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let (flag, inline) = match arg.split_once('=') {
+                Some((flag, value)) if flag.starts_with("--") => {
+                    (flag.to_string(), Some(value.to_string()))
+                }
+                _ => (arg.clone(), None),
+            };
+            let value = || {
+                inline
+                    .or_else(|| args.next())
+                    .ok_or_else(|| format!("option '{flag}' needs a value"))
+            };
+            match flag.as_str() {
+                "-h" | "--help" => parsed.command = "help".to_string(),
+                "-o" | "--output" => {
+                    parsed.format = match value()?.as_str() {
+                        "table" => Format::Table,
+                        "json" => Format::Json,
+                        other => return Err(format!("unknown output format '{other}'")),
+                    }
+                }
+                "-p" | "--property" => parsed.properties.extend(
+                    value()?
+                        .split(',')
+                        .filter(|name| !name.is_empty())
+                        .map(str::to_string),
+                ),
+                "-s" | "--signal" => parsed.signal = parse_signal(&value()?)?,
+                "--kill-whom" => {
+                    parsed.kill_whom = value()?;
+                    if !matches!(parsed.kill_whom.as_str(), "leader" | "all") {
+                        return Err(format!("unknown --kill-whom '{}'", parsed.kill_whom));
+                    }
+                }
+                "--no-ask-password" => parsed.ask_password = false,
+                _ if arg.starts_with('-') && arg.len() > 1 => {
+                    return Err(format!("unknown option '{arg}'"))
+                }
+                _ if parsed.command.is_empty() => parsed.command = arg,
+                _ => parsed.operands.push(arg),
+            }
+        }
+        if parsed.command.is_empty() {
+            parsed.command = "list-sessions".to_string();
+        }
+        Ok(parsed)
+    }
+}
+
+/// A signal number, or a name with or without the `SIG` prefix
+fn parse_signal(signal: &str) -> Result<i32, String> {
+    if let Ok(number) = signal.parse() {
+        return Ok(number);
+    }
+    Ok(match signal.trim_start_matches("SIG") {
+        "HUP" => libc::SIGHUP,
+        "INT" => libc::SIGINT,
+        "QUIT" => libc::SIGQUIT,
+        "KILL" => libc::SIGKILL,
+        "USR1" => libc::SIGUSR1,
+        "USR2" => libc::SIGUSR2,
+        "TERM" => libc::SIGTERM,
+        "CONT" => libc::SIGCONT,
+        "STOP" => libc::SIGSTOP,
+        _ => return Err(format!("unknown signal '{signal}'")),
+    })
+}
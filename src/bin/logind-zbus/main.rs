@@ -0,0 +1,467 @@
+//! `logind-zbus`, a small `loginctl` built on the proxies of this crate
+//!
+//! Useful where `loginctl` is missing or too old, and for scripts through `--output json`.
+
+use std::{
+    error::Error,
+    io::{self, Write},
+    process::ExitCode,
+};
+
+use logind_zbus::{
+    manager::ManagerProxyBlocking, seat::SeatProxyBlocking, session::SessionProxyBlocking,
+    user::UserProxyBlocking,
+};
+use serde_json::Value;
+use zbus::blocking::Connection;
+
+use crate::{
+    args::{Args, Format},
+    output::{Props, ToJson},
+};
+
+mod args;
+#[macro_use]
+mod output;
+#[cfg(all(test, feature = "testing"))]
+mod tests;
+
+type Result<T> = std::result::Result<T, Box<dyn Error>>;
+
+const USAGE: &str = "\
+Usage: logind-zbus [OPTIONS] COMMAND [ARGS...]
+
+Commands:
+  list-sessions                 List sessions
+  session-status [ID]           Show the status of a session
+  show-session [ID...]          Show properties of sessions, or of the manager
+  activate [ID]                 Activate a session
+  lock-session [ID...]          Lock sessions
+  unlock-session [ID...]        Unlock sessions
+  terminate-session ID...       Terminate sessions
+  kill-session ID...            Send a signal to the processes of sessions
+  list-users                    List logged in users
+  show-user [USER...]           Show properties of users, or of the manager
+  enable-linger [USER...]       Keep the service manager of users running after logout
+  disable-linger [USER...]      Stop the service manager of users at logout
+  terminate-user USER...        Terminate all sessions of users
+  kill-user USER...             Send a signal to all processes of users
+  list-seats                    List seats
+  show-seat [NAME...]           Show properties of seats, or of the manager
+  terminate-seat NAME...        Terminate all sessions on seats
+
+Options:
+  -o, --output table|json       Output format (default: table)
+  -p, --property NAME           Only show this property, may be repeated or comma separated
+  -s, --signal SIGNAL           Signal for kill-session and kill-user (default: TERM)
+      --kill-whom leader|all    Processes of a session to signal (default: all)
+      --no-ask-password         Do not ask for a password to authorize enable-linger and
+                                disable-linger
+  -h, --help                    Show this help
+";
+
+fn main() -> ExitCode {
+    let args = match Args::parse(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("logind-zbus: {e}");
+            eprint!("\n{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+    if args.command == "help" {
+        print!("{USAGE}");
+        return ExitCode::SUCCESS;
+    }
+
+    let result = Connection::system()
+        .map_err(Into::into)
+        .and_then(|connection| run(&connection, &args, &mut io::stdout().lock()));
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("logind-zbus: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Run the command in `args` against logind on `connection`, writing its output to `out`
+pub fn run(connection: &Connection, args: &Args, out: &mut dyn Write) -> Result<()> {
+    let manager = ManagerProxyBlocking::new(connection)?;
+    let operands = &args.operands;
+    match args.command.as_str() {
+        "list-sessions" => list_sessions(&manager, args.format, out),
+        "list-users" => list_users(&manager, args.format, out),
+        "list-seats" => list_seats(&manager, args.format, out),
+        // As with loginctl, these show the manager when given nothing to show
+        "show-session" | "show-user" | "show-seat" if operands.is_empty() => {
+            let props = output::filter(manager_props(&manager)?, &args.properties);
+            Ok(output::write_props(out, args.format, vec![props])?)
+        }
+        "show-session" => {
+            let objects = operands
+                .iter()
+                .map(|id| {
+                    Ok(output::filter(
+                        session_props(connection, &manager, id)?,
+                        &args.properties,
+                    ))
+                })
+                .collect::<Result<_>>()?;
+            Ok(output::write_props(out, args.format, objects)?)
+        }
+        "show-user" => {
+            let objects = operands
+                .iter()
+                .map(|user| {
+                    let uid = resolve_user(&manager, user)?;
+                    Ok(output::filter(
+                        user_props(connection, &manager, uid)?,
+                        &args.properties,
+                    ))
+                })
+                .collect::<Result<_>>()?;
+            Ok(output::write_props(out, args.format, objects)?)
+        }
+        "show-seat" => {
+            let objects = operands
+                .iter()
+                .map(|id| {
+                    Ok(output::filter(
+                        seat_props(connection, &manager, id)?,
+                        &args.properties,
+                    ))
+                })
+                .collect::<Result<_>>()?;
+            Ok(output::write_props(out, args.format, objects)?)
+        }
+        "session-status" => {
+            for id in or_auto(operands) {
+                let props = session_props(connection, &manager, id)?;
+                session_status(out, args.format, props)?;
+            }
+            Ok(())
+        }
+        "activate" => match operands.as_slice() {
+            [] => Ok(manager.activate_session("auto")?),
+            [id] => Ok(manager.activate_session(id)?),
+            _ => Err("activate takes at most one session".into()),
+        },
+        "lock-session" => {
+            for id in or_auto(operands) {
+                manager.lock_session(id)?;
+            }
+            Ok(())
+        }
+        "unlock-session" => {
+            for id in or_auto(operands) {
+                manager.unlock_session(id)?;
+            }
+            Ok(())
+        }
+        "terminate-session" => {
+            for id in required(operands, "session")? {
+                manager.terminate_session(id)?;
+            }
+            Ok(())
+        }
+        "kill-session" => {
+            for id in required(operands, "session")? {
+                manager.kill_session(id, &args.kill_whom, args.signal)?;
+            }
+            Ok(())
+        }
+        "enable-linger" | "disable-linger" => {
+            let enable = args.command == "enable-linger";
+            for uid in users_or_self(&manager, operands)? {
+                manager.set_user_linger(uid, enable, args.ask_password)?;
+            }
+            Ok(())
+        }
+        "terminate-user" => {
+            for user in required(operands, "user")? {
+                manager.terminate_user(resolve_user(&manager, user)?)?;
+            }
+            Ok(())
+        }
+        "kill-user" => {
+            for user in required(operands, "user")? {
+                manager.kill_user(resolve_user(&manager, user)?, args.signal)?;
+            }
+            Ok(())
+        }
+        "terminate-seat" => {
+            for id in required(operands, "seat")? {
+                manager.terminate_seat(id)?;
+            }
+            Ok(())
+        }
+        other => Err(format!("unknown command '{other}', see --help").into()),
+    }
+}
+
+fn list_sessions(
+    manager: &ManagerProxyBlocking,
+    format: Format,
+    out: &mut dyn Write,
+) -> Result<()> {
+    let rows = manager
+        .list_sessions_extended()?
+        .iter()
+        .map(|session| {
+            vec![
+                Value::from(session.sid()),
+                Value::from(session.uid()),
+                Value::from(session.user()),
+                Value::from(session.seat()),
+                Value::from(session.tty()),
+            ]
+        })
+        .collect();
+    let columns = ["SESSION", "UID", "USER", "SEAT", "TTY"];
+    Ok(output::write_table(out, format, &columns, rows, "session")?)
+}
+
+fn list_users(manager: &ManagerProxyBlocking, format: Format, out: &mut dyn Write) -> Result<()> {
+    let rows = manager
+        .list_users()?
+        .iter()
+        .map(|user| vec![Value::from(user.uid()), Value::from(user.name())])
+        .collect();
+    Ok(output::write_table(
+        out,
+        format,
+        &["UID", "USER"],
+        rows,
+        "user",
+    )?)
+}
+
+fn list_seats(manager: &ManagerProxyBlocking, format: Format, out: &mut dyn Write) -> Result<()> {
+    let rows = manager
+        .list_seats()?
+        .iter()
+        .map(|seat| vec![Value::from(seat.id())])
+        .collect();
+    Ok(output::write_table(out, format, &["SEAT"], rows, "seat")?)
+}
+
+fn manager_props(manager: &ManagerProxyBlocking) -> Result<Props> {
+    Ok(props!(manager, {
+        "EnableWallMessages" => enable_wall_messages,
+        "WallMessage" => wall_message,
+        "NAutoVTs" => n_auto_vts,
+        "KillOnlyUsers" => kill_only_users,
+        "KillExcludeUsers" => kill_exclude_users,
+        "KillUserProcesses" => kill_user_processes,
+        "RebootParameter" => reboot_parameter,
+        "RebootToFirmwareSetup" => reboot_to_firmware_setup,
+        "RebootToBootLoaderMenu" => reboot_to_boot_loader_menu,
+        "RebootToBootLoaderEntry" => reboot_to_boot_loader_entry,
+        "BootLoaderEntries" => boot_loader_entries,
+        "IdleHint" => idle_hint,
+        "IdleSinceHint" => idle_since_hint,
+        "IdleSinceHintMonotonic" => idle_since_hint_monotonic,
+        "BlockInhibited" => block_inhibited,
+        "DelayInhibited" => delay_inhibited,
+        "InhibitDelayMaxUSec" => inhibit_delay_max_USec,
+        "UserStopDelayUSec" => user_stop_delay_USec,
+        "StopIdleSessionUSec" => stop_idle_session_USec,
+        "HandlePowerKey" => handle_power_key,
+        "HandlePowerKeyLongPress" => handle_power_key_long_press,
+        "HandleRebootKey" => handle_reboot_key,
+        "HandleRebootKeyLongPress" => handle_reboot_key_long_press,
+        "HandleSuspendKey" => handle_suspend_key,
+        "HandleSuspendKeyLongPress" => handle_suspend_key_long_press,
+        "HandleHibernateKey" => handle_hibernate_key,
+        "HandleHibernateKeyLongPress" => handle_hibernate_key_long_press,
+        "HandleSecureAttentionKey" => handle_secure_attention_key,
+        "HandleLidSwitch" => handle_lid_switch,
+        "HandleLidSwitchExternalPower" => handle_lid_switch_external_power,
+        "HandleLidSwitchDocked" => handle_lid_switch_docked,
+        "HoldoffTimeoutUSec" => holdoff_timeout_USec,
+        "IdleAction" => idle_action,
+        "IdleActionUSec" => idle_action_USec,
+        "PreparingForShutdown" => preparing_for_shutdown,
+        "PreparingForSleep" => preparing_for_sleep,
+        "ScheduledShutdown" => scheduled_shutdown,
+        "DesignatedMaintenanceTime" => designated_maintenance_time,
+        "SleepOperation" => sleep_operation,
+        "Docked" => docked,
+        "LidClosed" => lid_closed,
+        "OnExternalPower" => on_external_power,
+        "RemoveIPC" => remove_IPC,
+        "RuntimeDirectorySize" => runtime_directory_size,
+        "RuntimeDirectoryInodesMax" => runtime_directory_inodes_max,
+        "InhibitorsMax" => inhibitors_max,
+        "NCurrentInhibitors" => NCurrent_inhibitors,
+        "SessionsMax" => sessions_max,
+        "NCurrentSessions" => NCurrent_sessions,
+    })?)
+}
+
+fn session_props(
+    connection: &Connection,
+    manager: &ManagerProxyBlocking,
+    id: &str,
+) -> Result<Props> {
+    let session = SessionProxyBlocking::builder(connection)
+        .path(manager.get_session(id)?)?
+        .build()?;
+    // Fail on a session that went away, rather than printing nothing
+    let mut props = vec![("Id", session.id()?.to_json())];
+    props.extend(props!(session, {
+        "User" => user,
+        "Name" => name,
+        "Timestamp" => timestamp,
+        "TimestampMonotonic" => timestamp_monotonic,
+        "VTNr" => VTNr,
+        "Seat" => seat,
+        "TTY" => TTY,
+        "Display" => display,
+        "Remote" => remote,
+        "RemoteHost" => remote_host,
+        "RemoteUser" => remote_user,
+        "Service" => service,
+        "Desktop" => desktop,
+        "Scope" => scope,
+        "Leader" => leader,
+        "Audit" => audit,
+        "Type" => type_,
+        "Class" => class,
+        "Active" => active,
+        "State" => state,
+        "IdleHint" => idle_hint,
+        "IdleSinceHint" => idle_since_hint,
+        "IdleSinceHintMonotonic" => idle_since_hint_monotonic,
+        "LockedHint" => locked_hint,
+    })?);
+    Ok(props)
+}
+
+fn user_props(connection: &Connection, manager: &ManagerProxyBlocking, uid: u32) -> Result<Props> {
+    let user = UserProxyBlocking::builder(connection)
+        .path(manager.get_user(uid)?)?
+        .build()?;
+    let mut props = vec![("UID", user.UID()?.to_json())];
+    props.extend(props!(user, {
+        "GID" => GID,
+        "Name" => name,
+        "Timestamp" => timestamp,
+        "TimestampMonotonic" => timestamp_monotonic,
+        "RuntimePath" => runtime_path,
+        "Service" => service,
+        "Slice" => slice,
+        "Display" => display,
+        "State" => state,
+        "Sessions" => sessions,
+        "IdleHint" => idle_hint,
+        "IdleSinceHint" => idle_since_hint,
+        "IdleSinceHintMonotonic" => idle_since_hint_monotonic,
+        "Linger" => linger,
+    })?);
+    Ok(props)
+}
+
+fn seat_props(connection: &Connection, manager: &ManagerProxyBlocking, id: &str) -> Result<Props> {
+    let seat = SeatProxyBlocking::builder(connection)
+        .path(manager.get_seat(id)?)?
+        .build()?;
+    let mut props = vec![("Id", seat.id()?.to_json())];
+    props.extend(props!(seat, {
+        "ActiveSession" => active_session,
+        "CanTTY" => can_TTY,
+        "CanGraphical" => can_graphical,
+        "Sessions" => sessions,
+        "IdleHint" => idle_hint,
+        "IdleSinceHint" => idle_since_hint,
+        "IdleSinceHintMonotonic" => idle_since_hint_monotonic,
+    })?);
+    Ok(props)
+}
+
+/// A summary of the session for people, or all its properties as JSON
+fn session_status(out: &mut dyn Write, format: Format, props: Props) -> Result<()> {
+    if format == Format::Json {
+        return Ok(output::write_props(out, format, vec![props])?);
+    }
+    let get = |name: &str| {
+        props
+            .iter()
+            .find(|(prop, _)| *prop == name)
+            .map(|(_, value)| value)
+            .unwrap_or(&Value::Null)
+    };
+    let text = |name: &str| output::text(get(name));
+    writeln!(out, "{} - {} ({})", text("Id"), text("Name"), text("User"))?;
+
+    let mut seat = text("Seat");
+    if get("VTNr").as_u64().unwrap_or_default() > 0 {
+        seat = format!("{}; vc{}", seat, text("VTNr"));
+    }
+    let since = get("Timestamp")
+        .as_u64()
+        .map(output::date)
+        .unwrap_or_default();
+    let rows = [
+        ("Since", since),
+        ("Leader", text("Leader")),
+        ("Seat", seat),
+        ("TTY", text("TTY")),
+        ("Display", text("Display")),
+        ("Remote", text("RemoteHost")),
+        ("Service", text("Service")),
+        ("Desktop", text("Desktop")),
+        ("Type", text("Type")),
+        ("Class", text("Class")),
+        ("State", text("State")),
+        ("Unit", text("Scope")),
+    ];
+    for (label, value) in rows {
+        if !value.is_empty() {
+            writeln!(out, "{:>8}: {}", label, value)?;
+        }
+    }
+    Ok(())
+}
+
+/// Session and seat commands act on the caller's own when given none
+fn or_auto(operands: &[String]) -> Vec<&str> {
+    if operands.is_empty() {
+        vec!["auto"]
+    } else {
+        operands.iter().map(String::as_str).collect()
+    }
+}
+
+fn required<'a>(operands: &'a [String], what: &str) -> Result<&'a [String]> {
+    if operands.is_empty() {
+        return Err(format!("no {what} given").into());
+    }
+    Ok(operands)
+}
+
+fn users_or_self(manager: &ManagerProxyBlocking, operands: &[String]) -> Result<Vec<u32>> {
+    if operands.is_empty() {
+        // SAFETY: getuid has no preconditions and cannot fail
+        return Ok(vec![unsafe { libc::getuid() }]);
+    }
+    operands
+        .iter()
+        .map(|user| resolve_user(manager, user))
+        .collect()
+}
+
+/// A UID, or the name of a logged in user
+fn resolve_user(manager: &ManagerProxyBlocking, user: &str) -> Result<u32> {
+    if let Ok(uid) = user.parse() {
+        return Ok(uid);
+    }
+    manager
+        .list_users()?
+        .iter()
+        .find(|info| info.name() == user)
+        .map(|info| info.uid())
+        .ok_or_else(|| format!("no logged in user named '{user}', give a UID instead").into())
+}
@@ -0,0 +1,319 @@
+//! Rendering of properties and listings as text tables or JSON
+//!
+//! Everything is collected as JSON values first, so both formats show the same data.
+
+use std::{
+    io::{self, Write},
+    time::UNIX_EPOCH,
+};
+
+use logind_zbus::{
+    manager::{
+        BootLoaderMenu, HandleAction, InhibitTypes, MaintenanceTime, ScheduledShutdown,
+        SleepOperation, TimeSpan,
+    },
+    seat::SessionPath,
+    session::{SessionClass, SessionState, SessionType, User},
+    user::UserState,
+    MonotonicStamp, RealtimeStamp, SomePath,
+};
+use serde_json::{Map, Value};
+use zbus::zvariant::OwnedObjectPath;
+
+use crate::args::Format;
+
+/// Property names and values, in the order they are shown
+pub type Props = Vec<(&'static str, Value)>;
+
+/// Conversion of property values to what is printed for them
+pub trait ToJson {
+    fn to_json(&self) -> Value;
+}
+
+macro_rules! to_json_via_from {
+    ($($type_name:ty),*) => {
+        $(impl ToJson for $type_name {
+            fn to_json(&self) -> Value {
+                Value::from(self.clone())
+            }
+        })*
+    };
+}
+to_json_via_from!(String, bool, u32, u64, Vec<String>);
+
+macro_rules! to_json_as_str {
+    ($($type_name:ty),*) => {
+        $(impl ToJson for $type_name {
+            fn to_json(&self) -> Value {
+                Value::from(self.as_str())
+            }
+        })*
+    };
+}
+to_json_as_str!(
+    HandleAction,
+    SessionClass,
+    SessionState,
+    SessionType,
+    UserState
+);
+
+/// Microseconds, as logind sends them
+impl ToJson for RealtimeStamp {
+    fn to_json(&self) -> Value {
+        Value::from(self.as_micros())
+    }
+}
+
+impl ToJson for MonotonicStamp {
+    fn to_json(&self) -> Value {
+        Value::from(self.as_micros())
+    }
+}
+
+impl ToJson for TimeSpan {
+    fn to_json(&self) -> Value {
+        Value::from(self.as_micros())
+    }
+}
+
+impl ToJson for BootLoaderMenu {
+    fn to_json(&self) -> Value {
+        Value::from(self.as_micros())
+    }
+}
+
+/// Colon separated, as logind sends them
+impl ToJson for InhibitTypes {
+    fn to_json(&self) -> Value {
+        Value::from(self.to_string())
+    }
+}
+
+/// The calendar event, or null when none is set
+impl ToJson for MaintenanceTime {
+    fn to_json(&self) -> Value {
+        Value::from(self.calendar_event())
+    }
+}
+
+impl ToJson for SleepOperation {
+    fn to_json(&self) -> Value {
+        self.modes()
+            .iter()
+            .map(|mode| Value::from(mode.as_str()))
+            .collect()
+    }
+}
+
+impl ToJson for SomePath {
+    fn to_json(&self) -> Value {
+        Value::from(self.id())
+    }
+}
+
+impl ToJson for SessionPath {
+    fn to_json(&self) -> Value {
+        Value::from(self.id())
+    }
+}
+
+impl ToJson for User {
+    fn to_json(&self) -> Value {
+        Value::from(self.uid())
+    }
+}
+
+/// The kind and realtime in microseconds, or null when nothing is scheduled
+impl ToJson for ScheduledShutdown {
+    fn to_json(&self) -> Value {
+        match self.clone().into_inner() {
+            Some((kind, time)) => {
+                let usec = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+                Value::from(vec![
+                    Value::from(kind.as_str()),
+                    Value::from(usec.as_micros() as u64),
+                ])
+            }
+            None => Value::Null,
+        }
+    }
+}
+
+/// The IDs of `Sessions` properties
+impl ToJson for Vec<(String, OwnedObjectPath)> {
+    fn to_json(&self) -> Value {
+        self.iter()
+            .map(|(id, _)| Value::from(id.as_str()))
+            .collect()
+    }
+}
+
+/// Collect the properties from a proxy's getters, leaving out those the running logind is
+/// too old to have. Any other failure to read a property is returned.
+macro_rules! props {
+    ($proxy:expr, { $($name:literal => $getter:ident,)* }) => {
+        (|| -> std::result::Result<$crate::output::Props, logind_zbus::Error> {
+            let mut props = $crate::output::Props::new();
+            $(match $proxy.$getter().map_err(logind_zbus::Error::from) {
+                Ok(value) => props.push(($name, $crate::output::ToJson::to_json(&value))),
+                Err(e) if e.is_unsupported() => {}
+                Err(e) => return Err(e),
+            })*
+            Ok(props)
+        })()
+    };
+}
+
+/// Keep the properties named in `names`, or all of them when it is empty
+pub fn filter(props: Props, names: &[String]) -> Props {
+    if names.is_empty() {
+        return props;
+    }
+    props
+        .into_iter()
+        .filter(|(name, _)| names.iter().any(|wanted| wanted == name))
+        .collect()
+}
+
+/// Print as `Name=value` lines with a blank line between objects, or as a JSON object
+/// (an array of them for several objects)
+pub fn write_props(out: &mut dyn Write, format: Format, objects: Vec<Props>) -> io::Result<()> {
+    match format {
+        Format::Table => {
+            for (i, props) in objects.iter().enumerate() {
+                if i > 0 {
+                    writeln!(out)?;
+                }
+                for (name, value) in props {
+                    writeln!(out, "{}={}", name, text(value))?;
+                }
+            }
+            Ok(())
+        }
+        Format::Json => {
+            let mut objects: Vec<Value> = objects.into_iter().map(object).collect();
+            let value = if objects.len() == 1 {
+                objects.remove(0)
+            } else {
+                Value::from(objects)
+            };
+            writeln!(out, "{}", value)
+        }
+    }
+}
+
+/// Print as an aligned table followed by a count of rows, or as a JSON array of objects
+/// keyed by the lowercased column names
+pub fn write_table(
+    out: &mut dyn Write,
+    format: Format,
+    columns: &[&str],
+    rows: Vec<Vec<Value>>,
+    noun: &str,
+) -> io::Result<()> {
+    match format {
+        Format::Table => {
+            let rows: Vec<Vec<String>> = rows
+                .iter()
+                .map(|row| row.iter().map(text).collect())
+                .collect();
+            let widths: Vec<usize> = columns
+                .iter()
+                .enumerate()
+                .map(|(i, column)| {
+                    rows.iter()
+                        .map(|row| row[i].len())
+                        .chain([column.len()])
+                        .max()
+                        .unwrap_or_default()
+                })
+                .collect();
+            let header: Vec<String> = columns.iter().map(|column| column.to_string()).collect();
+            for row in [header].iter().chain(&rows) {
+                let line: Vec<String> = row
+                    .iter()
+                    .zip(&widths)
+                    .map(|(cell, width)| format!("{cell:width$}"))
+                    .collect();
+                writeln!(out, "{}", line.join(" ").trim_end())?;
+            }
+            writeln!(
+                out,
+                "\n{} {}{} listed.",
+                rows.len(),
+                noun,
+                plural(rows.len())
+            )
+        }
+        Format::Json => {
+            let rows: Vec<Value> = rows
+                .into_iter()
+                .map(|row| {
+                    let props = columns
+                        .iter()
+                        .map(|column| column.to_lowercase())
+                        .zip(row)
+                        .collect::<Map<_, _>>();
+                    Value::Object(props)
+                })
+                .collect();
+            writeln!(out, "{}", Value::from(rows))
+        }
+    }
+}
+
+fn object(props: Props) -> Value {
+    Value::Object(
+        props
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value))
+            .collect(),
+    )
+}
+
+fn plural(count: usize) -> &'static str {
+    if count == 1 {
+        ""
+    } else {
+        "s"
+    }
+}
+
+/// A value as shown in text output: booleans as `yes`/`no` and lists space separated
+pub fn text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::Bool(true) => "yes".to_string(),
+        Value::Bool(false) => "no".to_string(),
+        Value::String(s) => s.clone(),
+        Value::Array(values) => values.iter().map(text).collect::<Vec<_>>().join(" "),
+        Value::Object(values) => values.values().map(text).collect::<Vec<_>>().join(" "),
+        Value::Number(n) => n.to_string(),
+    }
+}
+
+/// A realtime timestamp in microseconds as a UTC date, for human readable output
+pub fn date(usec: u64) -> String {
+    let secs = usec / 1_000_000;
+    let (days, rem) = (secs / 86_400, secs % 86_400);
+    // Days since the epoch to a civil date, after Howard Hinnant's `civil_from_days`
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+        year,
+        month,
+        day,
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60
+    )
+}
@@ -0,0 +1,153 @@
+use futures_lite::future;
+use logind_zbus::{
+    session::{SessionState, SessionType},
+    testing::{FakeLogind, FakeSeat, FakeSession, FakeUser},
+};
+use serde_json::{json, Value};
+use zbus::{
+    fdo,
+    zvariant::{OwnedValue, Value as DbusValue},
+};
+
+use crate::{args::Args, run};
+
+fn fake() -> FakeLogind {
+    future::block_on(async {
+        let fake = FakeLogind::new().await.unwrap();
+        fake.add_seat(FakeSeat::new("seat0")).await.unwrap();
+        fake.add_user(FakeUser::new(1000, "alice")).await.unwrap();
+        fake.add_session(FakeSession {
+            seat: Some("seat0".to_string()),
+            type_: SessionType::Wayland,
+            state: SessionState::Active,
+            tty: "tty2".to_string(),
+            vtnr: 2,
+            ..FakeSession::new("2", 1000)
+        })
+        .await
+        .unwrap();
+        fake
+    })
+}
+
+fn logind_zbus(fake: &FakeLogind, args: &[&str]) -> Result<String, String> {
+    let args = Args::parse(args.iter().map(|arg| arg.to_string()))?;
+    let mut out = Vec::new();
+    run(&fake.blocking_connection(), &args, &mut out).map_err(|e| e.to_string())?;
+    Ok(String::from_utf8(out).unwrap())
+}
+
+#[test]
+fn list() {
+    let fake = fake();
+
+    let table = logind_zbus(&fake, &["list-sessions"]).unwrap();
+    assert_eq!(
+        table,
+        "SESSION UID  USER  SEAT  TTY\n\
+         2       1000 alice seat0 tty2\n\
+         \n\
+         1 session listed.\n"
+    );
+
+    let json = logind_zbus(&fake, &["list-users", "--output=json"]).unwrap();
+    let json: Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(json, json!([{ "uid": 1000, "user": "alice" }]));
+}
+
+#[test]
+fn show() {
+    let fake = fake();
+
+    let text = logind_zbus(&fake, &["show-session", "2", "-p", "Type,Active"]).unwrap();
+    assert_eq!(text, "Type=wayland\nActive=yes\n");
+
+    let json = logind_zbus(
+        &fake,
+        &["show-user", "alice", "-o", "json", "-p", "Sessions"],
+    )
+    .unwrap();
+    let json: Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(json, json!({ "Sessions": ["2"] }));
+
+    let manager = logind_zbus(&fake, &["show-session", "-p", "NAutoVTs"]).unwrap();
+    assert_eq!(manager, "NAutoVTs=6\n");
+    for command in ["show-user", "show-seat"] {
+        let shown = logind_zbus(&fake, &[command, "-p", "NAutoVTs"]).unwrap();
+        assert_eq!(shown, manager);
+    }
+
+    let status = logind_zbus(&fake, &["session-status", "2"]).unwrap();
+    assert!(status.starts_with("2 - alice (1000)\n"), "{status}");
+    assert!(status.contains("    Seat: seat0; vc2\n"), "{status}");
+
+    let err = logind_zbus(&fake, &["show-seat", "seat9"]).unwrap_err();
+    assert!(err.contains("NoSuchSeat"), "{err}");
+
+    future::block_on(fake.update_manager(|m| m.missing = vec!["HandleRebootKey"])).unwrap();
+    let manager = logind_zbus(&fake, &["show-session"]).unwrap();
+    assert!(!manager.contains("HandleRebootKey="), "{manager}");
+    assert!(manager.contains("HandlePowerKey=poweroff\n"), "{manager}");
+}
+
+struct Getters;
+
+impl Getters {
+    fn linger(&self) -> zbus::Result<bool> {
+        Ok(true)
+    }
+
+    fn newer(&self) -> zbus::Result<bool> {
+        Err(fdo::Error::UnknownProperty("Unknown property Newer".to_string()).into())
+    }
+
+    fn denied(&self) -> zbus::Result<bool> {
+        Err(fdo::Error::AccessDenied("Access denied".to_string()).into())
+    }
+}
+
+#[test]
+fn unreadable_props() {
+    let props = props!(Getters, {
+        "Linger" => linger,
+        "Newer" => newer,
+    })
+    .unwrap();
+    assert_eq!(props, vec![("Linger", Value::from(true))]);
+
+    let err = props!(Getters, {
+        "Linger" => linger,
+        "Denied" => denied,
+    })
+    .unwrap_err();
+    assert!(matches!(err, logind_zbus::Error::AccessDenied(_)), "{err}");
+}
+
+#[test]
+fn actions() {
+    let fake = fake();
+
+    logind_zbus(&fake, &["kill-session", "2", "--signal", "SIGKILL"]).unwrap();
+    logind_zbus(&fake, &["enable-linger", "alice"]).unwrap();
+    let calls = fake.calls();
+    assert_eq!(calls[0].member, "KillSession");
+    assert_eq!(calls[0].args[1], OwnedValue::from(DbusValue::from("all")));
+    assert_eq!(calls[0].args[2], OwnedValue::from(libc::SIGKILL));
+    assert_eq!(calls[1].member, "SetUserLinger");
+    assert_eq!(calls[1].args[0], OwnedValue::from(1000u32));
+    assert_eq!(calls[1].args[2], OwnedValue::from(true));
+
+    logind_zbus(&fake, &["disable-linger", "alice", "--no-ask-password"]).unwrap();
+    let call = fake.calls().pop().unwrap();
+    assert_eq!(call.member, "SetUserLinger");
+    assert_eq!(call.args[1], OwnedValue::from(false));
+    assert_eq!(call.args[2], OwnedValue::from(false));
+
+    logind_zbus(&fake, &["terminate-session", "2"]).unwrap();
+    let table = logind_zbus(&fake, &["list-sessions"]).unwrap();
+    assert!(table.ends_with("0 sessions listed.\n"), "{table}");
+
+    assert!(logind_zbus(&fake, &["terminate-user"]).is_err());
+    assert!(logind_zbus(&fake, &["frobnicate"]).is_err());
+    assert!(logind_zbus(&fake, &["list-seats", "--output", "xml"]).is_err());
+}
@@ -0,0 +1,194 @@
+//! Typed errors for logind calls
+//!
+//! Proxy methods return `zbus::Result`. Their errors convert into [`Error`] with `?` or
+//! `map_err(Error::from)`, which picks out the error names logind and polkit reply with.
+
+use std::fmt;
+
+use zbus::fdo;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+macro_rules! dbus_errors {
+    ($($(#[$doc:meta])* $variant:ident => $name:literal,)*) => {
+        /// An error from logind, or from talking to it
+        ///
+        /// Errors logind replies with get their own variant holding the error message.
+        #[derive(Debug)]
+        #[non_exhaustive]
+        pub enum Error {
+            $($(#[$doc])* $variant(String),)*
+            /// A value from logind was not valid for the type it was parsed into
+            Parse(ParseError),
+            /// Any other D-Bus or connection error
+            Zbus(zbus::Error),
+        }
+
+        impl Error {
+            /// The D-Bus error name, if this came from an error reply
+            pub fn dbus_name(&self) -> Option<&str> {
+                match self {
+                    $(Self::$variant(_) => Some($name),)*
+                    Self::Zbus(zbus::Error::MethodError(name, _, _)) => Some(name.as_str()),
+                    _ => None,
+                }
+            }
+
+            fn from_dbus(name: &str, message: String) -> Option<Self> {
+                Some(match name {
+                    $($name => Self::$variant(message),)*
+                    _ => return None,
+                })
+            }
+        }
+
+        impl fmt::Display for Error {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                match self {
+                    $(Self::$variant(message) => write!(f, "{}: {}", $name, message),)*
+                    Self::Parse(e) => write!(f, "{}", e),
+                    Self::Zbus(e) => write!(f, "{}", e),
+                }
+            }
+        }
+    };
+}
+
+dbus_errors! {
+    /// No session with the given ID or path
+    NoSuchSession => "org.freedesktop.login1.NoSuchSession",
+    /// The process does not belong to a session
+    NoSessionForPID => "org.freedesktop.login1.NoSessionForPID",
+    /// The user is not logged in or lingering
+    NoSuchUser => "org.freedesktop.login1.NoSuchUser",
+    /// The process does not belong to a user
+    NoUserForPID => "org.freedesktop.login1.NoUserForPID",
+    /// No seat with the given ID or path
+    NoSuchSeat => "org.freedesktop.login1.NoSuchSeat",
+    /// The session is not on the seat it was asked about
+    SessionNotOnSeat => "org.freedesktop.login1.SessionNotOnSeat",
+    /// The caller is not the controller of the session
+    NotInControl => "org.freedesktop.login1.NotInControl",
+    /// The device is already taken by the session controller
+    DeviceIsTaken => "org.freedesktop.login1.DeviceIsTaken",
+    /// The device was never taken by the session controller
+    DeviceNotTaken => "org.freedesktop.login1.DeviceNotTaken",
+    /// Another power operation is already in progress
+    OperationInProgress => "org.freedesktop.login1.OperationInProgress",
+    /// The sleep operation is not supported by the system
+    SleepVerbNotSupported => "org.freedesktop.login1.SleepVerbNotSupported",
+    /// The session is busy, for example with another controller
+    SessionBusy => "org.freedesktop.login1.SessionBusy",
+    /// The device does not belong to the session's seat
+    NotYourDevice => "org.freedesktop.login1.NotYourDevice",
+    /// A shutdown has already been scheduled
+    ShutdownAlreadyScheduled => "org.freedesktop.login1.ShutdownAlreadyScheduled",
+    /// Not permitted by polkit
+    AccessDenied => "org.freedesktop.DBus.Error.AccessDenied",
+    /// Permitted by polkit only after authentication, and the call was not interactive
+    InteractiveAuthorizationRequired => "org.freedesktop.DBus.Error.InteractiveAuthorizationRequired",
+    /// The method is not known to the running logind, which is likely older than it
+    UnknownMethod => "org.freedesktop.DBus.Error.UnknownMethod",
+    /// The property is not known to the running logind, which is likely older than it
+    UnknownProperty => "org.freedesktop.DBus.Error.UnknownProperty",
+}
+
+impl Error {
+    /// Whether the running logind lacks the method or property, as with
+    /// [`Error::UnknownMethod`] and [`Error::UnknownProperty`]
+    pub fn is_unsupported(&self) -> bool {
+        matches!(self, Self::UnknownMethod(_) | Self::UnknownProperty(_))
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Parse(e) => Some(e),
+            Self::Zbus(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<zbus::Error> for Error {
+    fn from(e: zbus::Error) -> Self {
+        let error = match &e {
+            zbus::Error::MethodError(name, message, _) => {
+                Self::from_dbus(name.as_str(), message.clone().unwrap_or_default())
+            }
+            zbus::Error::FDO(fdo) => match &**fdo {
+                fdo::Error::AccessDenied(message) => Some(Self::AccessDenied(message.clone())),
+                fdo::Error::InteractiveAuthorizationRequired(message) => {
+                    Some(Self::InteractiveAuthorizationRequired(message.clone()))
+                }
+                fdo::Error::UnknownMethod(message) => Some(Self::UnknownMethod(message.clone())),
+                fdo::Error::UnknownProperty(message) => {
+                    Some(Self::UnknownProperty(message.clone()))
+                }
+                _ => None,
+            },
+            _ => None,
+        };
+        error.unwrap_or(Self::Zbus(e))
+    }
+}
+
+impl From<fdo::Error> for Error {
+    fn from(e: fdo::Error) -> Self {
+        zbus::Error::from(e).into()
+    }
+}
+
+impl From<ParseError> for Error {
+    fn from(e: ParseError) -> Self {
+        Self::Parse(e)
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Self::Zbus(e.into())
+    }
+}
+
+/// A string that is not one of the values of an enum in this crate
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    type_name: &'static str,
+    value: String,
+}
+
+impl ParseError {
+    #[doc(hidden)]
+    pub fn new(type_name: &'static str, value: &str) -> Self {
+        Self {
+            type_name,
+            value: value.to_string(),
+        }
+    }
+
+    /// The type that was being parsed
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+
+    /// The string that failed to parse
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "'{}' is not a valid {}", self.value, self.type_name)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl From<ParseError> for zbus::Error {
+    fn from(e: ParseError) -> Self {
+        zbus::Error::Variant(zbus::zvariant::Error::Message(e.to_string()))
+    }
+}
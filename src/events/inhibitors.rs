@@ -0,0 +1,144 @@
+//! Inhibitors coming and going
+//!
+//! logind has no signal for inhibitors. Taking or releasing one changes the
+//! `NCurrentInhibitors`, `BlockInhibited` and `DelayInhibited` properties, but logind does not
+//! send `PropertiesChanged` for them either. [`InhibitorWatcher`] therefore lists the inhibitors
+//! on a timer and reports the difference.
+
+use std::{
+    collections::VecDeque,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use futures_util::{
+    future,
+    stream::{self, BoxStream, Stream, StreamExt},
+};
+use zbus::Connection;
+
+use crate::manager::{Inhibitor, ManagerProxy};
+
+/// How often [`InhibitorWatcher::new`] lists the inhibitors
+pub const INHIBITOR_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// An inhibitor lock was taken or released
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InhibitorEvent {
+    InhibitorAdded(Inhibitor),
+    InhibitorRemoved(Inhibitor),
+}
+
+/// Stream of [`InhibitorEvent`]s
+///
+/// The inhibitors held when the watcher is created come first, as `InhibitorAdded`. Later
+/// changes are reported up to one polling interval after they happen.
+pub struct InhibitorWatcher {
+    inner: BoxStream<'static, InhibitorEvent>,
+}
+
+impl InhibitorWatcher {
+    /// List the inhibitors every [`INHIBITOR_POLL_INTERVAL`]
+    pub async fn new(connection: &Connection) -> zbus::Result<Self> {
+        Self::with_polling(connection, INHIBITOR_POLL_INTERVAL).await
+    }
+
+    /// List the inhibitors every `interval`
+    pub async fn with_polling(connection: &Connection, interval: Duration) -> zbus::Result<Self> {
+        let manager = ManagerProxy::new(connection).await?;
+        let refreshes = stream::once(future::ready(()))
+            .chain(async_io::Timer::interval(interval).map(|_| ()))
+            .boxed();
+
+        let watch = Watch {
+            manager,
+            refreshes,
+            known: Vec::new(),
+            pending: VecDeque::new(),
+        };
+        let inner = stream::unfold(watch, |mut watch| async move {
+            let event = watch.next().await?;
+            Some((event, watch))
+        })
+        .boxed();
+        Ok(Self { inner })
+    }
+}
+
+impl Stream for InhibitorWatcher {
+    type Item = InhibitorEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.inner.poll_next_unpin(cx)
+    }
+}
+
+/// Blocking iterator over [`InhibitorEvent`]s, see [`InhibitorWatcher`]
+pub struct InhibitorWatcherBlocking {
+    inner: InhibitorWatcher,
+}
+
+impl InhibitorWatcherBlocking {
+    pub fn new(connection: &zbus::blocking::Connection) -> zbus::Result<Self> {
+        let inner = zbus::block_on(InhibitorWatcher::new(connection.inner()))?;
+        Ok(Self { inner })
+    }
+
+    pub fn with_polling(
+        connection: &zbus::blocking::Connection,
+        interval: Duration,
+    ) -> zbus::Result<Self> {
+        let inner = zbus::block_on(InhibitorWatcher::with_polling(connection.inner(), interval))?;
+        Ok(Self { inner })
+    }
+}
+
+impl Iterator for InhibitorWatcherBlocking {
+    type Item = InhibitorEvent;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        zbus::block_on(self.inner.next())
+    }
+}
+
+/// The last listing, and the events from comparing it to the one before
+struct Watch {
+    manager: ManagerProxy<'static>,
+    refreshes: BoxStream<'static, ()>,
+    known: Vec<Inhibitor>,
+    pending: VecDeque<InhibitorEvent>,
+}
+
+impl Watch {
+    async fn next(&mut self) -> Option<InhibitorEvent> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Some(event);
+            }
+            self.refreshes.next().await?;
+            // A failed listing is retried on the next tick
+            if let Ok(current) = self.manager.list_inhibitors().await {
+                self.update(current);
+            }
+        }
+    }
+
+    /// Inhibitors have no ID, so identical ones are told apart only by how many there are
+    fn update(&mut self, current: Vec<Inhibitor>) {
+        let mut removed = std::mem::take(&mut self.known);
+        for inhibitor in &current {
+            match removed.iter().position(|known| known == inhibitor) {
+                Some(i) => {
+                    removed.remove(i);
+                }
+                None => self
+                    .pending
+                    .push_back(InhibitorEvent::InhibitorAdded(inhibitor.clone())),
+            }
+        }
+        self.pending
+            .extend(removed.into_iter().map(InhibitorEvent::InhibitorRemoved));
+        self.known = current;
+    }
+}
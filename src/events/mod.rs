@@ -0,0 +1,399 @@
+//! A single stream of everything logind signals
+//!
+//! [`LogindEvents`] listens to the manager and subscribes to the lock, unlock and property
+//! changes of every session, seat and user as they come and go, so callers don't have to
+//! juggle a stream per object. [`InhibitorWatcher`] does the same for inhibitor locks, which
+//! logind has no signals for, and [`ShutdownEvents`] reports each shutdown once, with its
+//! kind where logind sends it.
+
+use std::{
+    collections::HashMap,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use futures_util::stream::{self, AbortHandle, BoxStream, SelectAll, Stream, StreamExt};
+use zbus::{
+    fdo::{PropertiesChanged, PropertiesProxy},
+    zvariant::{OwnedObjectPath, OwnedValue},
+    Connection, Message,
+};
+
+use crate::{
+    manager::{
+        ManagerProxy, PrepareForShutdown, PrepareForShutdownWithMetadata, PrepareForSleep, SeatNew,
+        SeatRemoved, SecureAttentionKey, SessionNew, SessionRemoved, ShutdownMetadata, UserNew,
+        UserRemoved,
+    },
+    IntoPath,
+};
+
+mod inhibitors;
+mod shutdown;
+#[cfg(test)]
+mod tests;
+
+pub use inhibitors::*;
+pub use shutdown::*;
+
+pub(crate) const DESTINATION: &str = "org.freedesktop.login1";
+
+/// The logind object an event came from
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum LogindObject {
+    Manager,
+    Session(String),
+    Seat(String),
+    User(u32),
+}
+
+/// A signal from logind, from any of the objects it exports
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum LogindEvent {
+    SessionNew {
+        id: String,
+        path: OwnedObjectPath,
+    },
+    SessionRemoved {
+        id: String,
+        path: OwnedObjectPath,
+    },
+    SeatNew {
+        id: String,
+        path: OwnedObjectPath,
+    },
+    SeatRemoved {
+        id: String,
+        path: OwnedObjectPath,
+    },
+    UserNew {
+        uid: u32,
+        path: OwnedObjectPath,
+    },
+    UserRemoved {
+        uid: u32,
+        path: OwnedObjectPath,
+    },
+    /// `start` is true before the system goes to sleep, false after it woke up
+    PrepareForSleep {
+        start: bool,
+    },
+    /// `start` is true before shutdown, false if it was cancelled
+    PrepareForShutdown {
+        start: bool,
+    },
+    /// Sent by newer logind right after `PrepareForShutdown`, saying what kind of shutdown
+    PrepareForShutdownWithMetadata {
+        start: bool,
+        metadata: ShutdownMetadata,
+    },
+    /// The secure attention key was pressed on `seat`, and `HandleSecureAttentionKey` says
+    /// to tell the seat's session rather than act on it
+    SecureAttentionKey {
+        seat: String,
+        path: OwnedObjectPath,
+    },
+    /// The session was asked to lock its screen
+    Lock {
+        session: String,
+    },
+    /// The session was asked to unlock its screen
+    Unlock {
+        session: String,
+    },
+    /// Properties of `object` changed. Properties in `invalidated` changed too but their
+    /// new value was not sent, and has to be read if needed.
+    PropertiesChanged {
+        object: LogindObject,
+        changed: HashMap<String, OwnedValue>,
+        invalidated: Vec<String>,
+    },
+}
+
+/// Stream of [`LogindEvent`]s from the manager and every session, seat and user
+///
+/// Objects are subscribed to before their `*New` event is returned, so nothing they send
+/// after that is missed.
+pub struct LogindEvents {
+    inner: BoxStream<'static, LogindEvent>,
+}
+
+impl LogindEvents {
+    pub async fn new(connection: &Connection) -> zbus::Result<Self> {
+        Ok(Self::with_listing(connection).await?.0)
+    }
+
+    /// Also returns the sessions, seats and users that were listed to subscribe to them
+    pub(crate) async fn with_listing(
+        connection: &Connection,
+    ) -> zbus::Result<(Self, Vec<(LogindObject, OwnedObjectPath)>)> {
+        let manager = ManagerProxy::new(connection).await?;
+        let manager_signals = manager
+            .inner()
+            .receive_all_signals()
+            .await?
+            .filter_map(|msg| async move { manager_event(msg) })
+            .boxed();
+
+        let mut subscriptions = Subscriptions {
+            connection: connection.clone(),
+            streams: stream::select_all([manager_signals]),
+            attached: HashMap::new(),
+        };
+        subscriptions
+            .attach(
+                LogindObject::Manager,
+                manager.inner().path().to_owned().into(),
+            )
+            .await?;
+        // Subscribing to the manager first means objects created while these are listed
+        // also get a `*New` event, and attaching twice is a no-op
+        let mut listed = Vec::new();
+        for session in manager.list_sessions().await? {
+            let object = LogindObject::Session(session.sid().to_string());
+            listed.push((object, session.into_path()));
+        }
+        for seat in manager.list_seats().await? {
+            let object = LogindObject::Seat(seat.id().to_string());
+            listed.push((object, seat.path().clone()));
+        }
+        for user in manager.list_users().await? {
+            let object = LogindObject::User(user.uid());
+            listed.push((object, user.into_path()));
+        }
+        for (object, path) in &listed {
+            subscriptions.attach(object.clone(), path.clone()).await?;
+        }
+
+        let inner = stream::unfold(subscriptions, |mut subscriptions| async move {
+            let event = subscriptions.next().await?;
+            Some((event, subscriptions))
+        })
+        .boxed();
+        Ok((Self { inner }, listed))
+    }
+}
+
+impl Stream for LogindEvents {
+    type Item = LogindEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.inner.poll_next_unpin(cx)
+    }
+}
+
+/// Blocking iterator over [`LogindEvent`]s, see [`LogindEvents`]
+pub struct LogindEventsBlocking {
+    inner: LogindEvents,
+}
+
+impl LogindEventsBlocking {
+    pub fn new(connection: &zbus::blocking::Connection) -> zbus::Result<Self> {
+        let inner = zbus::block_on(LogindEvents::new(connection.inner()))?;
+        Ok(Self { inner })
+    }
+}
+
+impl Iterator for LogindEventsBlocking {
+    type Item = LogindEvent;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        zbus::block_on(self.inner.next())
+    }
+}
+
+/// The merged signal streams, and handles to drop those of objects that went away
+struct Subscriptions {
+    connection: Connection,
+    streams: SelectAll<BoxStream<'static, LogindEvent>>,
+    attached: HashMap<LogindObject, AbortHandle>,
+}
+
+impl Subscriptions {
+    async fn next(&mut self) -> Option<LogindEvent> {
+        let event = self.streams.next().await?;
+        let (object, path) = match &event {
+            LogindEvent::SessionNew { id, path } => (LogindObject::Session(id.clone()), path),
+            LogindEvent::SeatNew { id, path } => (LogindObject::Seat(id.clone()), path),
+            LogindEvent::UserNew { uid, path } => (LogindObject::User(*uid), path),
+            LogindEvent::SessionRemoved { id, .. } => {
+                self.detach(&LogindObject::Session(id.clone()));
+                return Some(event);
+            }
+            LogindEvent::SeatRemoved { id, .. } => {
+                self.detach(&LogindObject::Seat(id.clone()));
+                return Some(event);
+            }
+            LogindEvent::UserRemoved { uid, .. } => {
+                self.detach(&LogindObject::User(*uid));
+                return Some(event);
+            }
+            _ => return Some(event),
+        };
+        // Subscribing only fails with the connection, and the event is still worth returning
+        let _ = self.attach(object, path.clone()).await;
+        Some(event)
+    }
+
+    async fn attach(&mut self, object: LogindObject, path: OwnedObjectPath) -> zbus::Result<()> {
+        if self.attached.contains_key(&object) {
+            return Ok(());
+        }
+        let properties = PropertiesProxy::builder(&self.connection)
+            .destination(DESTINATION)?
+            .path(path.clone())?
+            .build()
+            .await?;
+        let mut streams = vec![{
+            let object = object.clone();
+            properties
+                .receive_properties_changed()
+                .await?
+                .filter_map(move |signal| {
+                    let event = properties_event(object.clone(), signal);
+                    async move { event }
+                })
+                .boxed()
+        }];
+        if let LogindObject::Session(id) = &object {
+            let session = zbus::Proxy::new(
+                &self.connection,
+                DESTINATION,
+                path,
+                "org.freedesktop.login1.Session",
+            )
+            .await?;
+            let id = id.clone();
+            streams.push(
+                session
+                    .receive_all_signals()
+                    .await?
+                    .filter_map(move |msg| {
+                        let event = session_event(&id, &msg);
+                        async move { event }
+                    })
+                    .boxed(),
+            );
+        }
+
+        let (streams, handle) = stream::abortable(stream::select_all(streams));
+        self.streams.push(streams.boxed());
+        self.attached.insert(object, handle);
+        Ok(())
+    }
+
+    fn detach(&mut self, object: &LogindObject) {
+        if let Some(handle) = self.attached.remove(object) {
+            handle.abort();
+        }
+    }
+}
+
+fn manager_event(msg: Arc<Message>) -> Option<LogindEvent> {
+    let member = msg.member()?;
+    Some(match member.as_str() {
+        "SessionNew" => {
+            let signal = SessionNew::from_message(msg)?;
+            let args = signal.args().ok()?;
+            LogindEvent::SessionNew {
+                id: args.session_id().to_string(),
+                path: args.object_path().clone(),
+            }
+        }
+        "SessionRemoved" => {
+            let signal = SessionRemoved::from_message(msg)?;
+            let args = signal.args().ok()?;
+            LogindEvent::SessionRemoved {
+                id: args.session_id().to_string(),
+                path: args.object_path().clone(),
+            }
+        }
+        "SeatNew" => {
+            let signal = SeatNew::from_message(msg)?;
+            let args = signal.args().ok()?;
+            LogindEvent::SeatNew {
+                id: args.seat_id().to_string(),
+                path: args.object_path().clone(),
+            }
+        }
+        "SeatRemoved" => {
+            let signal = SeatRemoved::from_message(msg)?;
+            let args = signal.args().ok()?;
+            LogindEvent::SeatRemoved {
+                id: args.seat_id().to_string(),
+                path: args.object_path().clone(),
+            }
+        }
+        "UserNew" => {
+            let signal = UserNew::from_message(msg)?;
+            let args = signal.args().ok()?;
+            LogindEvent::UserNew {
+                uid: *args.uid(),
+                path: args.object_path().clone(),
+            }
+        }
+        "UserRemoved" => {
+            let signal = UserRemoved::from_message(msg)?;
+            let args = signal.args().ok()?;
+            LogindEvent::UserRemoved {
+                uid: *args.uid(),
+                path: args.object_path().clone(),
+            }
+        }
+        "PrepareForSleep" => {
+            let signal = PrepareForSleep::from_message(msg)?;
+            let start = *signal.args().ok()?.start();
+            LogindEvent::PrepareForSleep { start }
+        }
+        "PrepareForShutdown" => {
+            let signal = PrepareForShutdown::from_message(msg)?;
+            let start = *signal.args().ok()?.start();
+            LogindEvent::PrepareForShutdown { start }
+        }
+        "PrepareForShutdownWithMetadata" => {
+            let signal = PrepareForShutdownWithMetadata::from_message(msg)?;
+            let args = signal.args().ok()?;
+            LogindEvent::PrepareForShutdownWithMetadata {
+                start: *args.start(),
+                metadata: args.metadata().clone().into(),
+            }
+        }
+        "SecureAttentionKey" => {
+            let signal = SecureAttentionKey::from_message(msg)?;
+            let args = signal.args().ok()?;
+            LogindEvent::SecureAttentionKey {
+                seat: args.seat_id().to_string(),
+                path: args.object_path().clone(),
+            }
+        }
+        _ => return None,
+    })
+}
+
+fn session_event(id: &str, msg: &Message) -> Option<LogindEvent> {
+    let session = id.to_string();
+    match msg.member()?.as_str() {
+        "Lock" => Some(LogindEvent::Lock { session }),
+        "Unlock" => Some(LogindEvent::Unlock { session }),
+        _ => None,
+    }
+}
+
+fn properties_event(object: LogindObject, signal: PropertiesChanged) -> Option<LogindEvent> {
+    let args = signal.args().ok()?;
+    Some(LogindEvent::PropertiesChanged {
+        object,
+        changed: args
+            .changed_properties()
+            .iter()
+            .map(|(name, value)| (name.to_string(), OwnedValue::from(value)))
+            .collect(),
+        invalidated: args
+            .invalidated_properties()
+            .iter()
+            .map(|name| name.to_string())
+            .collect(),
+    })
+}
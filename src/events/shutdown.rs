@@ -0,0 +1,109 @@
+//! Shutdowns being prepared, with what kind they are when logind says
+//!
+//! logind since systemd 255 sends `PrepareForShutdownWithMetadata` right after each
+//! `PrepareForShutdown`, with the kind of shutdown in its metadata. [`ShutdownEvents`] follows
+//! whichever of the two the running logind has, so every shutdown is reported once.
+
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures_util::{
+    future,
+    stream::{BoxStream, Stream, StreamExt},
+};
+use zbus::Connection;
+
+use crate::manager::{ManagerProxy, ShutdownKind, ShutdownMetadata};
+
+const WITH_METADATA: &str = "PrepareForShutdownWithMetadata";
+
+/// A shutdown is about to start, or was cancelled
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShutdownEvent {
+    /// True before shutdown, false if it was cancelled
+    pub start: bool,
+    /// `None` from a logind without `PrepareForShutdownWithMetadata`
+    pub metadata: Option<ShutdownMetadata>,
+}
+
+impl ShutdownEvent {
+    /// The kind of shutdown, when logind said
+    pub fn kind(&self) -> Option<&ShutdownKind> {
+        self.metadata.as_ref()?.kind()
+    }
+}
+
+/// Stream of [`ShutdownEvent`]s
+pub struct ShutdownEvents {
+    inner: BoxStream<'static, ShutdownEvent>,
+}
+
+impl ShutdownEvents {
+    /// Listen to `PrepareForShutdownWithMetadata` if the manager's introspection data has it,
+    /// and to `PrepareForShutdown` otherwise
+    pub async fn new(connection: &Connection) -> zbus::Result<Self> {
+        let manager = ManagerProxy::new(connection).await?;
+        let introspection = manager.inner().introspect().await?;
+        let inner = if declares_signal(&introspection, WITH_METADATA) {
+            manager
+                .receive_prepare_for_shutdown_with_metadata()
+                .await?
+                .filter_map(|signal| {
+                    let event = signal.args().ok().map(|args| ShutdownEvent {
+                        start: *args.start(),
+                        metadata: Some(args.metadata().clone().into()),
+                    });
+                    future::ready(event)
+                })
+                .boxed()
+        } else {
+            manager
+                .receive_prepare_for_shutdown()
+                .await?
+                .filter_map(|signal| {
+                    let event = signal.args().ok().map(|args| ShutdownEvent {
+                        start: *args.start(),
+                        metadata: None,
+                    });
+                    future::ready(event)
+                })
+                .boxed()
+        };
+        Ok(Self { inner })
+    }
+}
+
+/// Whether the introspection XML has a signal called `name`
+pub(super) fn declares_signal(introspection: &str, name: &str) -> bool {
+    introspection.contains(&format!("<signal name=\"{name}\""))
+}
+
+impl Stream for ShutdownEvents {
+    type Item = ShutdownEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.inner.poll_next_unpin(cx)
+    }
+}
+
+/// Blocking iterator over [`ShutdownEvent`]s, see [`ShutdownEvents`]
+pub struct ShutdownEventsBlocking {
+    inner: ShutdownEvents,
+}
+
+impl ShutdownEventsBlocking {
+    pub fn new(connection: &zbus::blocking::Connection) -> zbus::Result<Self> {
+        let inner = zbus::block_on(ShutdownEvents::new(connection.inner()))?;
+        Ok(Self { inner })
+    }
+}
+
+impl Iterator for ShutdownEventsBlocking {
+    type Item = ShutdownEvent;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        zbus::block_on(self.inner.next())
+    }
+}
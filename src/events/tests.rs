@@ -0,0 +1,221 @@
+use std::time::Duration;
+
+use crate::manager::{InhibitType, ManagerProxy, Mode, ShutdownKind};
+use crate::session::SessionState;
+use crate::testing::{self, FakeInhibitor, FakeSession};
+use futures_lite::{future, StreamExt};
+use zbus::zvariant::OwnedValue;
+
+use super::{
+    shutdown::declares_signal, InhibitorEvent, InhibitorWatcher, InhibitorWatcherBlocking,
+    LogindEvent, LogindEvents, LogindEventsBlocking, LogindObject, ShutdownEvents,
+    ShutdownEventsBlocking,
+};
+
+#[test]
+fn events() {
+    future::block_on(async {
+        let fake = testing::desktop().await;
+        let manager = ManagerProxy::new(fake.connection()).await.unwrap();
+        let mut events = LogindEvents::new(fake.connection()).await.unwrap();
+
+        // Sessions that existed before the stream was created are subscribed to
+        manager.lock_session("2").await.unwrap();
+        let event = next_signal(&mut events).await;
+        assert_eq!(
+            event,
+            LogindEvent::Lock {
+                session: "2".to_string()
+            }
+        );
+
+        fake.add_session(FakeSession::new("c1", 1000))
+            .await
+            .unwrap();
+        let event = next_signal(&mut events).await;
+        assert!(
+            matches!(&event, LogindEvent::SessionNew { id, .. } if id == "c1"),
+            "{event:?}"
+        );
+
+        // and so are new ones, as soon as they are announced
+        fake.update_session("c1", |s| s.state = SessionState::Active)
+            .await
+            .unwrap();
+        let event = next_from(&mut events, LogindObject::Session("c1".to_string())).await;
+        let LogindEvent::PropertiesChanged { changed, .. } = event else {
+            panic!("{event:?}");
+        };
+        assert_eq!(changed["Active"], OwnedValue::from(true));
+        manager.unlock_session("c1").await.unwrap();
+        let event = next_signal(&mut events).await;
+        assert_eq!(
+            event,
+            LogindEvent::Unlock {
+                session: "c1".to_string()
+            }
+        );
+
+        fake.remove_session("c1").await.unwrap();
+        let event = next_signal(&mut events).await;
+        assert!(
+            matches!(&event, LogindEvent::SessionRemoved { id, .. } if id == "c1"),
+            "{event:?}"
+        );
+
+        fake.prepare_for_sleep(true).await.unwrap();
+        let event = next_signal(&mut events).await;
+        assert_eq!(event, LogindEvent::PrepareForSleep { start: true });
+
+        fake.secure_attention_key("seat0").await.unwrap();
+        let event = next_signal(&mut events).await;
+        assert!(
+            matches!(&event, LogindEvent::SecureAttentionKey { seat, path }
+                if seat == "seat0" && path.as_str() == "/org/freedesktop/login1/seat/seat0"),
+            "{event:?}"
+        );
+    })
+}
+
+#[test]
+fn events_blocking() {
+    let fake = future::block_on(testing::desktop());
+    let connection = fake.blocking_connection();
+    let mut events = LogindEventsBlocking::new(&connection).unwrap();
+
+    future::block_on(fake.prepare_for_shutdown(true)).unwrap();
+    let event = events
+        .find(|event| !matches!(event, LogindEvent::PropertiesChanged { .. }))
+        .unwrap();
+    assert_eq!(event, LogindEvent::PrepareForShutdown { start: true });
+}
+
+fn player() -> FakeInhibitor {
+    FakeInhibitor {
+        what: InhibitType::Idle.into(),
+        who: "player".to_string(),
+        why: "Playing a video".to_string(),
+        mode: Mode::Block,
+        uid: 1000,
+        pid: 4242,
+    }
+}
+
+#[test]
+fn inhibitor_watcher() {
+    future::block_on(async {
+        let fake = testing::desktop().await;
+        fake.add_inhibitor(player()).await.unwrap();
+        let manager = ManagerProxy::new(fake.connection()).await.unwrap();
+        let mut watcher =
+            InhibitorWatcher::with_polling(fake.connection(), Duration::from_millis(20))
+                .await
+                .unwrap();
+
+        // Inhibitors held already come first
+        let Some(InhibitorEvent::InhibitorAdded(held)) = watcher.next().await else {
+            panic!("no inhibitor");
+        };
+        assert_eq!(held.who(), "player");
+
+        let lock = manager
+            .inhibit(
+                InhibitType::Sleep.into(),
+                "updater",
+                "Installing",
+                Mode::Delay,
+            )
+            .await
+            .unwrap();
+        let Some(InhibitorEvent::InhibitorAdded(taken)) = watcher.next().await else {
+            panic!("lock not reported");
+        };
+        assert_eq!(taken.who(), "updater");
+        assert_eq!(taken.mode(), &Mode::Delay);
+
+        lock.release();
+        let event = watcher.next().await.unwrap();
+        assert_eq!(event, InhibitorEvent::InhibitorRemoved(taken));
+    })
+}
+
+#[test]
+fn inhibitor_watcher_polling() {
+    let fake = future::block_on(testing::desktop());
+    let connection = fake.blocking_connection();
+    let mut watcher =
+        InhibitorWatcherBlocking::with_polling(&connection, Duration::from_millis(20)).unwrap();
+
+    future::block_on(fake.add_inhibitor(player())).unwrap();
+    let event = watcher.next().unwrap();
+    assert!(
+        matches!(&event, InhibitorEvent::InhibitorAdded(i) if i.process_id() == 4242),
+        "{event:?}"
+    );
+    future::block_on(fake.clear_inhibitors()).unwrap();
+    let event = watcher.next().unwrap();
+    assert!(
+        matches!(event, InhibitorEvent::InhibitorRemoved(_)),
+        "{event:?}"
+    );
+}
+
+#[test]
+fn shutdown_events() {
+    future::block_on(async {
+        let fake = testing::desktop().await;
+        let mut events = ShutdownEvents::new(fake.connection()).await.unwrap();
+
+        // Each shutdown comes once, although the fake sends both signals as logind does
+        fake.prepare_for_shutdown_as(true, ShutdownKind::Reboot)
+            .await
+            .unwrap();
+        fake.prepare_for_shutdown(false).await.unwrap();
+        let event = events.next().await.unwrap();
+        assert!(event.start);
+        assert_eq!(event.kind(), Some(&ShutdownKind::Reboot));
+        assert!(event.metadata.unwrap().is_reboot());
+        let event = events.next().await.unwrap();
+        assert!(!event.start);
+        assert_eq!(event.kind(), Some(&ShutdownKind::PowerOff));
+    })
+}
+
+#[test]
+fn shutdown_events_blocking() {
+    let fake = future::block_on(testing::desktop());
+    let connection = fake.blocking_connection();
+    let mut events = ShutdownEventsBlocking::new(&connection).unwrap();
+
+    future::block_on(fake.prepare_for_shutdown_as(true, ShutdownKind::SoftReboot)).unwrap();
+    let event = events.next().unwrap();
+    assert_eq!(event.kind(), Some(&ShutdownKind::SoftReboot));
+
+    // A logind from before systemd 255 gets `PrepareForShutdown` followed instead
+    let old = r#"<interface name="org.freedesktop.login1.Manager">
+  <signal name="PrepareForShutdown">
+   <arg type="b"/>
+  </signal>
+  <signal name="PrepareForSleep">
+   <arg type="b"/>
+  </signal>
+ </interface>"#;
+    assert!(declares_signal(old, "PrepareForShutdown"));
+    assert!(!declares_signal(old, "PrepareForShutdownWithMetadata"));
+}
+
+/// Skip property changes, which may come in any order relative to other signals
+async fn next_signal(events: &mut LogindEvents) -> LogindEvent {
+    events
+        .find(|event| !matches!(event, LogindEvent::PropertiesChanged { .. }))
+        .await
+        .unwrap()
+}
+
+/// Skip to the next property change of `object`
+async fn next_from(events: &mut LogindEvents, object: LogindObject) -> LogindEvent {
+    events
+        .find(|event| matches!(event, LogindEvent::PropertiesChanged { object: o, .. } if *o == object))
+        .await
+        .unwrap()
+}
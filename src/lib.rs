@@ -1,17 +1,25 @@
 //! Reference <https://www.freedesktop.org/software/systemd/man/org.freedesktop.login1.html>
 
 use std::{
-    ops::{Deref, DerefMut},
-    time::Duration,
+    io,
+    os::unix::io::{AsRawFd, BorrowedFd, OwnedFd},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
 use serde::{Deserialize, Serialize};
 use zbus::zvariant::{OwnedObjectPath, OwnedValue, Structure, Type};
+pub mod error;
+pub mod events;
 pub mod manager;
 pub mod seat;
 pub mod session;
+pub mod state;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
 pub mod user;
 
+pub use error::{Error, ParseError};
+
 //const DEFAULT_DEST: &str = "org.freedesktop.login1";
 
 pub trait IntoPath {
@@ -19,28 +27,130 @@ pub trait IntoPath {
     fn into_path_ref(&self) -> &OwnedObjectPath;
 }
 
-pub struct TimeStamp(Duration);
+/// Take our own copy of an fd received in a message, as the message closes its copy when dropped
+pub(crate) fn dup_fd(fd: &impl AsRawFd) -> io::Result<OwnedFd> {
+    // SAFETY: the caller holds the message, and with it the fd, while this runs
+    unsafe { BorrowedFd::borrow_raw(fd.as_raw_fd()) }.try_clone_to_owned()
+}
+
+/// Read `clock`, which must be one the system has
+fn clock_now(clock: libc::clockid_t) -> Duration {
+    let mut ts = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    // SAFETY: `ts` is a valid timespec to write to
+    unsafe { libc::clock_gettime(clock, &mut ts) };
+    Duration::new(ts.tv_sec as u64, ts.tv_nsec as u32)
+}
+
+/// A `CLOCK_REALTIME` time in microseconds since the epoch, as logind sends them
+///
+/// Zero means the event never happened, so the conversions return `None` for it.
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Type, Serialize, Deserialize,
+)]
+pub struct RealtimeStamp(u64);
+
+impl RealtimeStamp {
+    pub fn from_micros(usec: u64) -> Self {
+        Self(usec)
+    }
+
+    pub fn as_micros(&self) -> u64 {
+        self.0
+    }
+
+    pub fn to_system_time(&self) -> Option<SystemTime> {
+        (self.0 != 0).then(|| UNIX_EPOCH + Duration::from_micros(self.0))
+    }
+
+    /// Time passed since then, zero if it is in the future
+    pub fn elapsed(&self) -> Option<Duration> {
+        let time = self.to_system_time()?;
+        Some(SystemTime::now().duration_since(time).unwrap_or_default())
+    }
+}
+
+impl From<u64> for RealtimeStamp {
+    fn from(usec: u64) -> Self {
+        Self(usec)
+    }
+}
+
+impl TryFrom<OwnedValue> for RealtimeStamp {
+    type Error = zbus::Error;
+
+    fn try_from(value: OwnedValue) -> Result<Self, Self::Error> {
+        Ok(Self(<u64>::try_from(value)?))
+    }
+}
+
+/// A `CLOCK_MONOTONIC` time in microseconds since boot, as logind sends them
+///
+/// Unlike [`RealtimeStamp`] it is unaffected by changes to the system clock. Zero means the
+/// event never happened, so the conversions return `None` for it.
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Type, Serialize, Deserialize,
+)]
+pub struct MonotonicStamp(u64);
+
+impl MonotonicStamp {
+    pub fn from_micros(usec: u64) -> Self {
+        Self(usec)
+    }
+
+    pub fn as_micros(&self) -> u64 {
+        self.0
+    }
 
-impl Deref for TimeStamp {
-    type Target = Duration;
+    /// The same point in time as an `Instant`, which is also based on `CLOCK_MONOTONIC`
+    pub fn to_instant(&self) -> Option<Instant> {
+        if self.0 == 0 {
+            return None;
+        }
+        let (now, since_boot) = (Instant::now(), clock_now(libc::CLOCK_MONOTONIC));
+        let stamp = Duration::from_micros(self.0);
+        match since_boot.checked_sub(stamp) {
+            Some(ago) => now.checked_sub(ago),
+            None => now.checked_add(stamp - since_boot),
+        }
+    }
+
+    /// The wall clock time this happened, assuming the system clock has not been changed since
+    pub fn to_system_time(&self) -> Option<SystemTime> {
+        if self.0 == 0 {
+            return None;
+        }
+        let (now, since_boot) = (SystemTime::now(), clock_now(libc::CLOCK_MONOTONIC));
+        let stamp = Duration::from_micros(self.0);
+        match since_boot.checked_sub(stamp) {
+            Some(ago) => now.checked_sub(ago),
+            None => now.checked_add(stamp - since_boot),
+        }
+    }
 
-    fn deref(&self) -> &Self::Target {
-        &self.0
+    /// Time passed since then, zero if it is in the future
+    pub fn elapsed(&self) -> Option<Duration> {
+        if self.0 == 0 {
+            return None;
+        }
+        let since_boot = clock_now(libc::CLOCK_MONOTONIC);
+        Some(since_boot.saturating_sub(Duration::from_micros(self.0)))
     }
 }
 
-impl DerefMut for TimeStamp {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0
+impl From<u64> for MonotonicStamp {
+    fn from(usec: u64) -> Self {
+        Self(usec)
     }
 }
 
-impl TryFrom<OwnedValue> for TimeStamp {
+impl TryFrom<OwnedValue> for MonotonicStamp {
     type Error = zbus::Error;
 
     fn try_from(value: OwnedValue) -> Result<Self, Self::Error> {
-        let value = <u64>::try_from(value)?;
-        return Ok(Self(Duration::from_micros(value)));
+        Ok(Self(<u64>::try_from(value)?))
     }
 }
 
@@ -106,49 +216,74 @@ macro_rules! impl_try_from_owned_as_str {
 
             fn try_from(value: OwnedValue) -> Result<Self, Self::Error> {
                 let value = <String>::try_from(value)?;
-                return Ok($type_name::from_str(value.as_str())?);
+                Ok($type_name::from(value.as_str()))
             }
         }
     };
 }
 
+/// String conversions for an enum whose values logind may add to. Strings that aren't
+/// listed are kept in the enum's `Other(String)` variant.
 #[macro_export]
 macro_rules! enum_impl_str_conv {
     ($type_name:ident, { $($label:tt : $variant:tt,)* }) => {
+        impl $type_name {
+            pub fn as_str(&self) -> &str {
+                match self {
+                    $($type_name::$variant => $label,)+
+                    $type_name::Other(s) => s.as_str(),
+                }
+            }
+        }
+
+        impl From<&str> for $type_name {
+            fn from(m: &str) -> Self {
+                match m {
+                    $($label => $type_name::$variant,)+
+                    _ => $type_name::Other(m.to_string()),
+                }
+            }
+        }
+
         impl FromStr for $type_name {
-            type Err = fdo::Error;
+            type Err = std::convert::Infallible;
 
             fn from_str(m: &str) -> Result<Self, Self::Err> {
-                let res = match m {
-                    $($label => $type_name::$variant,)+
-                    _ => return Err(fdo::Error::IOError(format!("{} is an invalid variant", m))),
-                };
-                Ok(res)
+                Ok($type_name::from(m))
             }
         }
 
-        impl From<$type_name> for &str {
-            fn from(m: $type_name) -> Self {
-                match m {
-                    $($type_name::$variant => $label,)+
+        impl<'a> From<&'a $type_name> for &'a str {
+            fn from(s: &'a $type_name) -> Self {
+                s.as_str()
+            }
+        }
+
+        impl From<$type_name> for String {
+            fn from(s: $type_name) -> Self {
+                match s {
+                    $type_name::Other(s) => s,
+                    s => s.as_str().to_string(),
                 }
             }
         }
 
-        impl From<&$type_name> for &str {
-            fn from(s: &$type_name) -> Self {
-                <&str>::from(*s)
+        impl std::fmt::Display for $type_name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str(self.as_str())
             }
         }
 }}
 
 #[cfg(test)]
 mod tests {
-    use crate::{manager::ManagerProxyBlocking, session::SessionProxyBlocking};
+    use crate::{manager::ManagerProxyBlocking, session::SessionProxyBlocking, testing};
+    use futures_lite::future;
 
     #[test]
     fn basic_test() {
-        let connection = zbus::blocking::Connection::system().unwrap();
+        let fake = future::block_on(testing::desktop());
+        let connection = fake.blocking_connection();
         let manager = ManagerProxyBlocking::new(&connection).unwrap();
         let sessions = manager.list_sessions().unwrap();
         let session_proxy = SessionProxyBlocking::builder(&connection)
@@ -0,0 +1,106 @@
+//! Everything the `Can*` methods answer, in one go
+
+use futures_util::join;
+
+use crate::error::{Error, Result};
+
+use super::{IsSupported, ManagerProxy, ManagerProxyBlocking, PowerAction};
+
+/// The answers of all the `Can*` methods at one point in time
+///
+/// A method the running logind does not have yet is `None`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PowerCapabilities {
+    pub halt: Option<IsSupported>,
+    pub hibernate: Option<IsSupported>,
+    pub hybrid_sleep: Option<IsSupported>,
+    pub power_off: Option<IsSupported>,
+    pub reboot: Option<IsSupported>,
+    pub reboot_parameter: Option<IsSupported>,
+    pub reboot_to_boot_loader_entry: Option<IsSupported>,
+    pub reboot_to_boot_loader_menu: Option<IsSupported>,
+    pub reboot_to_firmware_setup: Option<IsSupported>,
+    pub sleep: Option<IsSupported>,
+    pub suspend: Option<IsSupported>,
+    pub suspend_then_hibernate: Option<IsSupported>,
+}
+
+impl PowerCapabilities {
+    /// The answer for `action`
+    pub fn get(&self, action: PowerAction) -> Option<&IsSupported> {
+        match action {
+            PowerAction::PowerOff => self.power_off.as_ref(),
+            PowerAction::Reboot => self.reboot.as_ref(),
+            PowerAction::Halt => self.halt.as_ref(),
+            PowerAction::Suspend => self.suspend.as_ref(),
+            PowerAction::Hibernate => self.hibernate.as_ref(),
+            PowerAction::HybridSleep => self.hybrid_sleep.as_ref(),
+            PowerAction::SuspendThenHibernate => self.suspend_then_hibernate.as_ref(),
+            PowerAction::Sleep => self.sleep.as_ref(),
+        }
+    }
+}
+
+/// `None` for a method logind does not have
+fn optional(answer: zbus::Result<IsSupported>) -> Result<Option<IsSupported>> {
+    match answer.map_err(Error::from) {
+        Ok(answer) => Ok(Some(answer)),
+        Err(Error::UnknownMethod(_)) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+impl ManagerProxy<'_> {
+    /// Ask all the `Can*` methods at once
+    pub async fn power_capabilities(&self) -> Result<PowerCapabilities> {
+        let (
+            halt,
+            hibernate,
+            hybrid_sleep,
+            power_off,
+            reboot,
+            reboot_parameter,
+            reboot_to_boot_loader_entry,
+            reboot_to_boot_loader_menu,
+            reboot_to_firmware_setup,
+            sleep,
+            suspend,
+            suspend_then_hibernate,
+        ) = join!(
+            self.can_halt(),
+            self.can_hibernate(),
+            self.can_hybrid_sleep(),
+            self.can_power_off(),
+            self.can_reboot(),
+            self.can_reboot_parameter(),
+            self.can_reboot_to_boot_loader_entry(),
+            self.can_reboot_to_boot_loader_menu(),
+            self.can_reboot_to_firmware_setup(),
+            self.can_sleep(),
+            self.can_suspend(),
+            self.can_suspend_then_hibernate(),
+        );
+        Ok(PowerCapabilities {
+            halt: optional(halt)?,
+            hibernate: optional(hibernate)?,
+            hybrid_sleep: optional(hybrid_sleep)?,
+            power_off: optional(power_off)?,
+            reboot: optional(reboot)?,
+            reboot_parameter: optional(reboot_parameter)?,
+            reboot_to_boot_loader_entry: optional(reboot_to_boot_loader_entry)?,
+            reboot_to_boot_loader_menu: optional(reboot_to_boot_loader_menu)?,
+            reboot_to_firmware_setup: optional(reboot_to_firmware_setup)?,
+            sleep: optional(sleep)?,
+            suspend: optional(suspend)?,
+            suspend_then_hibernate: optional(suspend_then_hibernate)?,
+        })
+    }
+}
+
+impl ManagerProxyBlocking<'_> {
+    /// Ask all the `Can*` methods at once
+    pub fn power_capabilities(&self) -> Result<PowerCapabilities> {
+        let manager = ManagerProxy::from(self.inner().inner().clone());
+        zbus::block_on(manager.power_capabilities())
+    }
+}
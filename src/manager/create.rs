@@ -0,0 +1,273 @@
+//! Typed `CreateSession`, for PAM modules, greeters and login daemons
+
+use std::os::unix::io::{AsFd, BorrowedFd, OwnedFd};
+
+use serde::Deserialize;
+use zbus::zvariant::{self, OwnedObjectPath, OwnedValue, Signature, Type, Value};
+
+use crate::{
+    dup_fd,
+    session::{SessionClass, SessionType},
+};
+
+use super::{ManagerProxy, ManagerProxyBlocking};
+
+/// Arguments for [`ManagerProxy::create_session_with`]
+///
+/// Everything except the user and leader process is optional and defaults to empty.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionCreateRequest {
+    uid: u32,
+    pid: u32,
+    service: String,
+    type_: SessionType,
+    class: SessionClass,
+    desktop: String,
+    seat_id: String,
+    vtnr: u32,
+    tty: String,
+    display: String,
+    remote: bool,
+    remote_user: String,
+    remote_host: String,
+    properties: Vec<(String, OwnedValue)>,
+}
+
+impl SessionCreateRequest {
+    /// A session for `uid` with `pid` as its leader process. A `pid` of 0 means the caller.
+    pub fn new(uid: u32, pid: u32) -> Self {
+        Self {
+            uid,
+            pid,
+            service: String::new(),
+            type_: SessionType::Unspecified,
+            class: SessionClass::User,
+            desktop: String::new(),
+            seat_id: String::new(),
+            vtnr: 0,
+            tty: String::new(),
+            display: String::new(),
+            remote: false,
+            remote_user: String::new(),
+            remote_host: String::new(),
+            properties: Vec::new(),
+        }
+    }
+
+    /// The PAM service name, such as `gdm-password`
+    pub fn service(mut self, service: impl Into<String>) -> Self {
+        self.service = service.into();
+        self
+    }
+
+    pub fn session_type(mut self, type_: SessionType) -> Self {
+        self.type_ = type_;
+        self
+    }
+
+    pub fn class(mut self, class: SessionClass) -> Self {
+        self.class = class;
+        self
+    }
+
+    /// The desktop environment, such as `GNOME`
+    pub fn desktop(mut self, desktop: impl Into<String>) -> Self {
+        self.desktop = desktop.into();
+        self
+    }
+
+    pub fn seat(mut self, seat_id: impl Into<String>) -> Self {
+        self.seat_id = seat_id.into();
+        self
+    }
+
+    /// The virtual terminal number, for sessions on a seat with VTs
+    pub fn vtnr(mut self, vtnr: u32) -> Self {
+        self.vtnr = vtnr;
+        self
+    }
+
+    pub fn tty(mut self, tty: impl Into<String>) -> Self {
+        self.tty = tty.into();
+        self
+    }
+
+    /// The X11 display name
+    pub fn display(mut self, display: impl Into<String>) -> Self {
+        self.display = display.into();
+        self
+    }
+
+    pub fn remote(mut self, remote: bool) -> Self {
+        self.remote = remote;
+        self
+    }
+
+    pub fn remote_user(mut self, remote_user: impl Into<String>) -> Self {
+        self.remote_user = remote_user.into();
+        self
+    }
+
+    pub fn remote_host(mut self, remote_host: impl Into<String>) -> Self {
+        self.remote_host = remote_host.into();
+        self
+    }
+
+    /// Add one of the extra properties logind accepts, such as `"StopOnIdle"` or `"RuntimeMaxUSec"`
+    pub fn property<'v>(mut self, name: impl Into<String>, value: impl Into<Value<'v>>) -> Self {
+        self.properties
+            .push((name.into(), OwnedValue::from(value.into())));
+        self
+    }
+
+    fn properties(&self) -> Vec<(&str, Value<'_>)> {
+        self.properties
+            .iter()
+            .map(|(name, value)| (name.as_str(), Value::from(value)))
+            .collect()
+    }
+}
+
+/// A session registered with `CreateSession`
+///
+/// logind ends the session once the last copy of the FIFO fd is closed, so the session
+/// lasts as long as this (or the fd taken out of it with [`CreatedSession::into_fifo`]).
+#[derive(Debug)]
+pub struct CreatedSession {
+    session_id: String,
+    object_path: OwnedObjectPath,
+    runtime_path: String,
+    fifo: OwnedFd,
+    uid: u32,
+    seat_id: String,
+    vtnr: u32,
+    existing: bool,
+}
+
+impl CreatedSession {
+    pub fn session_id(&self) -> &str {
+        &self.session_id
+    }
+
+    pub fn object_path(&self) -> &OwnedObjectPath {
+        &self.object_path
+    }
+
+    /// The user's runtime directory, for `XDG_RUNTIME_DIR`
+    pub fn runtime_path(&self) -> &str {
+        &self.runtime_path
+    }
+
+    pub fn uid(&self) -> u32 {
+        self.uid
+    }
+
+    pub fn seat_id(&self) -> &str {
+        &self.seat_id
+    }
+
+    pub fn vtnr(&self) -> u32 {
+        self.vtnr
+    }
+
+    /// The caller was already part of this session and no new one was created
+    pub fn existing(&self) -> bool {
+        self.existing
+    }
+
+    pub fn into_fifo(self) -> OwnedFd {
+        self.fifo
+    }
+}
+
+impl AsFd for CreatedSession {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.fifo.as_fd()
+    }
+}
+
+impl Type for CreatedSession {
+    fn signature() -> Signature<'static> {
+        Signature::from_static_str_unchecked("(soshusub)")
+    }
+}
+
+impl<'de> Deserialize<'de> for CreatedSession {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let (session_id, object_path, runtime_path, fifo, uid, seat_id, vtnr, existing) =
+            <(
+                String,
+                OwnedObjectPath,
+                String,
+                zvariant::Fd,
+                u32,
+                String,
+                u32,
+                bool,
+            )>::deserialize(deserializer)?;
+        Ok(Self {
+            session_id,
+            object_path,
+            runtime_path,
+            fifo: dup_fd(&fifo).map_err(serde::de::Error::custom)?,
+            uid,
+            seat_id,
+            vtnr,
+            existing,
+        })
+    }
+}
+
+impl ManagerProxy<'_> {
+    /// Register a new session, as `pam_systemd` does
+    pub async fn create_session_with(
+        &self,
+        request: &SessionCreateRequest,
+    ) -> zbus::Result<CreatedSession> {
+        self.create_session(
+            request.uid,
+            request.pid,
+            &request.service,
+            request.type_.clone(),
+            request.class.clone(),
+            &request.desktop,
+            &request.seat_id,
+            request.vtnr,
+            &request.tty,
+            &request.display,
+            request.remote,
+            &request.remote_user,
+            &request.remote_host,
+            &request.properties(),
+        )
+        .await
+    }
+}
+
+impl ManagerProxyBlocking<'_> {
+    /// Register a new session, as `pam_systemd` does
+    pub fn create_session_with(
+        &self,
+        request: &SessionCreateRequest,
+    ) -> zbus::Result<CreatedSession> {
+        self.create_session(
+            request.uid,
+            request.pid,
+            &request.service,
+            request.type_.clone(),
+            request.class.clone(),
+            &request.desktop,
+            &request.seat_id,
+            request.vtnr,
+            &request.tty,
+            &request.display,
+            request.remote,
+            &request.remote_user,
+            &request.remote_host,
+            &request.properties(),
+        )
+    }
+}
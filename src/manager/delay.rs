@@ -0,0 +1,281 @@
+//! Helpers for running cleanup before the system sleeps or shuts down
+//!
+//! A delay inhibitor lets a program hold off sleep or shutdown for up to
+//! `InhibitDelayMaxUSec` while it saves state. The guards here take the lock,
+//! wait for the `PrepareFor*` signal, run a callback, release the lock, and
+//! take it again once the system is back.
+
+use std::{
+    future::Future,
+    marker::PhantomData,
+    sync::mpsc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use futures_util::{
+    future::{self, select, Either},
+    pin_mut, StreamExt,
+};
+use zbus::{blocking::SignalIterator, Message, SignalStream};
+
+use crate::error::Result;
+
+use super::{InhibitType, InhibitorLock, ManagerProxy, ManagerProxyBlocking, Mode};
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// What a [`DelayGuard`] is delaying. Implemented by [`ForSleep`] and [`ForShutdown`].
+pub trait DelayTarget: sealed::Sealed {
+    #[doc(hidden)]
+    const INHIBIT: InhibitType;
+    #[doc(hidden)]
+    const SIGNAL: &'static str;
+}
+
+/// Marker for guards that delay sleep
+#[derive(Debug)]
+pub struct ForSleep;
+
+impl sealed::Sealed for ForSleep {}
+impl DelayTarget for ForSleep {
+    const INHIBIT: InhibitType = InhibitType::Sleep;
+    const SIGNAL: &'static str = "PrepareForSleep";
+}
+
+/// Marker for guards that delay shutdown
+#[derive(Debug)]
+pub struct ForShutdown;
+
+impl sealed::Sealed for ForShutdown {}
+impl DelayTarget for ForShutdown {
+    const INHIBIT: InhibitType = InhibitType::Shutdown;
+    const SIGNAL: &'static str = "PrepareForShutdown";
+}
+
+/// Delays sleep until a callback has run
+pub type SleepGuard<'a> = DelayGuard<'a, ForSleep>;
+/// Delays shutdown until a callback has run
+pub type ShutdownGuard<'a> = DelayGuard<'a, ForShutdown>;
+/// Blocking version of [`SleepGuard`]
+pub type SleepGuardBlocking<'a> = DelayGuardBlocking<'a, ForSleep>;
+/// Blocking version of [`ShutdownGuard`]
+pub type ShutdownGuardBlocking<'a> = DelayGuardBlocking<'a, ForShutdown>;
+
+/// Timing of a callback run by a delay guard
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DelayReport {
+    /// How long the callback ran for
+    pub elapsed: Duration,
+    /// The deadline it had, from `InhibitDelayMaxUSec`
+    pub deadline: Duration,
+}
+
+impl DelayReport {
+    /// The callback did not finish before the deadline and the lock was released without it
+    pub fn overran(&self) -> bool {
+        self.elapsed >= self.deadline
+    }
+}
+
+/// Returned by `next()` on the delay guards
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DelayEvent {
+    /// The system is about to sleep or shut down. The callback has run and the lock is released.
+    Prepared(DelayReport),
+    /// The system is back (or a shutdown was cancelled) and the lock has been taken again
+    Resumed,
+}
+
+/// Holds a delay inhibitor and runs a callback each time logind is about to act on it
+///
+/// Use through the [`SleepGuard`] and [`ShutdownGuard`] aliases.
+pub struct DelayGuard<'a, K: DelayTarget> {
+    manager: ManagerProxy<'a>,
+    signals: SignalStream<'a>,
+    who: String,
+    why: String,
+    deadline: Duration,
+    lock: Option<InhibitorLock>,
+    _target: PhantomData<K>,
+}
+
+impl<'a, K: DelayTarget> DelayGuard<'a, K> {
+    /// Take the delay lock and start listening for the `PrepareFor*` signal
+    pub async fn new(manager: &ManagerProxy<'a>, who: &str, why: &str) -> Result<Self> {
+        let manager = manager.clone();
+        // Subscribe first so that a signal sent right after the lock is taken isn't missed
+        let signals = manager.receive_signal(K::SIGNAL).await?;
+        // An infinite delay leaves the callback all the time it needs
+        let deadline = manager.inhibit_delay_max().await?.unwrap_or(Duration::MAX);
+        let lock = manager
+            .inhibit(K::INHIBIT.into(), who, why, Mode::Delay)
+            .await?;
+        Ok(Self {
+            manager,
+            signals,
+            who: who.to_string(),
+            why: why.to_string(),
+            deadline,
+            lock: Some(lock),
+            _target: PhantomData,
+        })
+    }
+
+    /// How long a callback is given before the lock is released regardless
+    pub fn deadline(&self) -> Duration {
+        self.deadline
+    }
+
+    /// Whether the delay lock is currently held
+    pub fn is_held(&self) -> bool {
+        self.lock.is_some()
+    }
+
+    /// Wait for the next `PrepareFor*` signal.
+    ///
+    /// When the system is about to act, `callback` is run and the lock released once it
+    /// completes or the deadline passes, whichever comes first. A callback that overruns
+    /// is dropped. When the system is back, the lock is taken again.
+    pub async fn next<F, Fut>(&mut self, callback: F) -> Result<DelayEvent>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = ()>,
+    {
+        loop {
+            let message = self
+                .signals
+                .next()
+                .await
+                .ok_or_else(|| zbus::Error::Failure(format!("{} stream ended", K::SIGNAL)))?;
+            match (signal_start(&message)?, self.lock.take()) {
+                (true, Some(lock)) => {
+                    let start = Instant::now();
+                    let callback = callback();
+                    pin_mut!(callback);
+                    // An infinite deadline is never reached, so there is no timer for it
+                    let timer = match start.checked_add(self.deadline) {
+                        Some(at) => Either::Left(async_io::Timer::at(at)),
+                        None => Either::Right(future::pending()),
+                    };
+                    if let Either::Right(_) = select(callback, timer).await {
+                        drop(lock);
+                        return Ok(DelayEvent::Prepared(DelayReport {
+                            elapsed: start.elapsed().max(self.deadline),
+                            deadline: self.deadline,
+                        }));
+                    }
+                    let elapsed = start.elapsed();
+                    drop(lock);
+                    return Ok(DelayEvent::Prepared(DelayReport {
+                        elapsed,
+                        deadline: self.deadline,
+                    }));
+                }
+                (false, None) => {
+                    let lock = self
+                        .manager
+                        .inhibit(K::INHIBIT.into(), &self.who, &self.why, Mode::Delay)
+                        .await?;
+                    self.lock = Some(lock);
+                    return Ok(DelayEvent::Resumed);
+                }
+                // Repeated signal for a state we're already in
+                (_, lock) => self.lock = lock,
+            }
+        }
+    }
+}
+
+/// Blocking version of [`DelayGuard`]
+///
+/// Use through the [`SleepGuardBlocking`] and [`ShutdownGuardBlocking`] aliases.
+pub struct DelayGuardBlocking<'a, K: DelayTarget> {
+    manager: ManagerProxyBlocking<'a>,
+    signals: SignalIterator<'a>,
+    who: String,
+    why: String,
+    deadline: Duration,
+    lock: Option<InhibitorLock>,
+    _target: PhantomData<K>,
+}
+
+impl<'a, K: DelayTarget> DelayGuardBlocking<'a, K> {
+    /// Take the delay lock and start listening for the `PrepareFor*` signal
+    pub fn new(manager: &ManagerProxyBlocking<'a>, who: &str, why: &str) -> Result<Self> {
+        let manager = manager.clone();
+        let signals = manager.receive_signal(K::SIGNAL)?;
+        let deadline = manager.inhibit_delay_max()?.unwrap_or(Duration::MAX);
+        let lock = manager.inhibit(K::INHIBIT.into(), who, why, Mode::Delay)?;
+        Ok(Self {
+            manager,
+            signals,
+            who: who.to_string(),
+            why: why.to_string(),
+            deadline,
+            lock: Some(lock),
+            _target: PhantomData,
+        })
+    }
+
+    /// How long a callback is given before the lock is released regardless
+    pub fn deadline(&self) -> Duration {
+        self.deadline
+    }
+
+    /// Whether the delay lock is currently held
+    pub fn is_held(&self) -> bool {
+        self.lock.is_some()
+    }
+
+    /// Wait for the next `PrepareFor*` signal.
+    ///
+    /// When the system is about to act, `callback` is run and the lock released once it
+    /// returns or the deadline passes, whichever comes first. An overrunning callback still
+    /// runs to completion but no longer holds up the system. When the system is back, the
+    /// lock is taken again.
+    pub fn next<F>(&mut self, callback: F) -> Result<DelayEvent>
+    where
+        F: FnOnce(),
+    {
+        loop {
+            let message = self
+                .signals
+                .next()
+                .ok_or_else(|| zbus::Error::Failure(format!("{} stream ended", K::SIGNAL)))?;
+            match (signal_start(&message)?, self.lock.take()) {
+                (true, Some(lock)) => {
+                    let deadline = self.deadline;
+                    let (done, finished) = mpsc::channel::<()>();
+                    let watchdog = thread::spawn(move || {
+                        let _ = finished.recv_timeout(deadline);
+                        drop(lock);
+                    });
+                    let start = Instant::now();
+                    callback();
+                    let elapsed = start.elapsed();
+                    drop(done);
+                    let _ = watchdog.join();
+                    return Ok(DelayEvent::Prepared(DelayReport { elapsed, deadline }));
+                }
+                (false, None) => {
+                    let lock = self.manager.inhibit(
+                        K::INHIBIT.into(),
+                        &self.who,
+                        &self.why,
+                        Mode::Delay,
+                    )?;
+                    self.lock = Some(lock);
+                    return Ok(DelayEvent::Resumed);
+                }
+                (_, lock) => self.lock = lock,
+            }
+        }
+    }
+}
+
+fn signal_start(message: &Message) -> zbus::Result<bool> {
+    message.body::<bool>()
+}
@@ -0,0 +1,158 @@
+//! Finding out why the system won't suspend, power off or reboot
+//!
+//! Whether a power action goes ahead depends on the matching `Can*` method, the blocking
+//! inhibitors, and whether logind is already in the middle of sleeping or shutting down.
+//! [`PowerDiagnostics::explain`] gathers all of that into one [`PowerReport`].
+
+use std::{
+    fmt,
+    time::{Duration, SystemTime},
+};
+
+use crate::error::Result;
+
+use super::{
+    Inhibitor, IsSupported, ManagerProxy, ManagerProxyBlocking, Mode, PowerAction, ShutdownKind,
+};
+
+/// Everything that decides whether a [`PowerAction`] can happen right now
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PowerReport {
+    pub action: PowerAction,
+    /// What the matching `Can*` method answered
+    pub can: IsSupported,
+    /// Inhibitors in `block` or `block-weak` mode that hold this action off
+    pub blockers: Vec<Inhibitor>,
+    /// logind is already preparing to sleep or shut down, depending on the action
+    pub in_progress: bool,
+    /// The shutdown scheduled with `ScheduleShutdown`, if any
+    pub scheduled_shutdown: Option<(ShutdownKind, SystemTime)>,
+}
+
+impl PowerReport {
+    /// Whether the action would go ahead right now without asking for authentication
+    pub fn is_allowed(&self) -> bool {
+        self.can == IsSupported::Yes && self.blockers.is_empty() && !self.in_progress
+    }
+
+    /// Whether the action would go ahead once the user authenticates
+    pub fn needs_authentication(&self) -> bool {
+        self.can == IsSupported::Challenge && self.blockers.is_empty() && !self.in_progress
+    }
+}
+
+/// One reason per line, for pasting into a support ticket
+impl fmt::Display for PowerReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_allowed() {
+            writeln!(f, "{} is possible", self.action)?;
+        } else {
+            writeln!(f, "{} is not possible right now", self.action)?;
+        }
+        match &self.can {
+            IsSupported::Yes => {}
+            IsSupported::Challenge => writeln!(f, "- authentication is required")?,
+            IsSupported::No => writeln!(f, "- the user is not allowed to")?,
+            IsSupported::NA => writeln!(f, "- this system does not support it")?,
+            IsSupported::Other(can) => writeln!(f, "- logind answered '{can}'")?,
+        }
+        for blocker in &self.blockers {
+            writeln!(
+                f,
+                "- blocked by '{}' (uid {}, pid {}): {}",
+                blocker.who(),
+                blocker.user_id(),
+                blocker.process_id(),
+                blocker.why()
+            )?;
+        }
+        if self.in_progress {
+            let what = if self.action.is_sleep() {
+                "sleep"
+            } else {
+                "shutdown"
+            };
+            writeln!(f, "- the system is already preparing for {what}")?;
+        }
+        if let Some((kind, time)) = &self.scheduled_shutdown {
+            match time.duration_since(SystemTime::now()) {
+                Ok(left) => writeln!(f, "- {kind} is scheduled in {}s", round_secs(left))?,
+                Err(_) => writeln!(f, "- {kind} was scheduled and is due")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+fn round_secs(duration: Duration) -> u64 {
+    duration.as_secs() + u64::from(duration.subsec_millis() >= 500)
+}
+
+fn blockers(inhibitors: Vec<Inhibitor>, action: PowerAction) -> Vec<Inhibitor> {
+    let inhibit_type = action.inhibit_type();
+    inhibitors
+        .into_iter()
+        .filter(|i| matches!(i.mode(), Mode::Block | Mode::BlockWeak))
+        .filter(|i| i.what().contains(&inhibit_type))
+        .collect()
+}
+
+/// Explains what stands in the way of power actions
+pub struct PowerDiagnostics<'a> {
+    manager: ManagerProxy<'a>,
+}
+
+impl<'a> PowerDiagnostics<'a> {
+    pub fn new(manager: &ManagerProxy<'a>) -> Self {
+        Self {
+            manager: manager.clone(),
+        }
+    }
+
+    pub async fn explain(&self, action: PowerAction) -> Result<PowerReport> {
+        let manager = &self.manager;
+        let can = manager.can(action).await?;
+        let in_progress = if action.is_sleep() {
+            manager.preparing_for_sleep().await?
+        } else {
+            manager.preparing_for_shutdown().await?
+        };
+        Ok(PowerReport {
+            action,
+            can,
+            blockers: blockers(manager.list_inhibitors().await?, action),
+            in_progress,
+            scheduled_shutdown: manager.scheduled_shutdown().await?.into_inner(),
+        })
+    }
+}
+
+/// Blocking version of [`PowerDiagnostics`]
+pub struct PowerDiagnosticsBlocking<'a> {
+    manager: ManagerProxyBlocking<'a>,
+}
+
+impl<'a> PowerDiagnosticsBlocking<'a> {
+    pub fn new(manager: &ManagerProxyBlocking<'a>) -> Self {
+        Self {
+            manager: manager.clone(),
+        }
+    }
+
+    pub fn explain(&self, action: PowerAction) -> Result<PowerReport> {
+        let manager = &self.manager;
+        let can = manager.can(action)?;
+        let in_progress = if action.is_sleep() {
+            manager.preparing_for_sleep()?
+        } else {
+            manager.preparing_for_shutdown()?
+        };
+        Ok(PowerReport {
+            action,
+            can,
+            blockers: blockers(manager.list_inhibitors()?, action),
+            in_progress,
+            scheduled_shutdown: manager.scheduled_shutdown()?.into_inner(),
+        })
+    }
+}
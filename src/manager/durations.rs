@@ -0,0 +1,62 @@
+//! The `*USec` properties as `Duration`s
+//!
+//! `None` stands for infinity. The generated `*_USec` getters still give the raw
+//! [`TimeSpan`](super::TimeSpan).
+
+use std::time::Duration;
+
+use super::{ManagerProxy, ManagerProxyBlocking};
+
+impl ManagerProxy<'_> {
+    /// How long a delay inhibitor may hold off sleep or shutdown
+    pub async fn inhibit_delay_max(&self) -> zbus::Result<Option<Duration>> {
+        Ok(self.inhibit_delay_max_USec().await?.to_duration())
+    }
+
+    /// How long lid switches are ignored after boot or resume
+    pub async fn holdoff_timeout(&self) -> zbus::Result<Option<Duration>> {
+        Ok(self.holdoff_timeout_USec().await?.to_duration())
+    }
+
+    /// How long the system is idle before `IdleAction` is taken
+    pub async fn idle_action_delay(&self) -> zbus::Result<Option<Duration>> {
+        Ok(self.idle_action_USec().await?.to_duration())
+    }
+
+    /// How long a user's service manager is kept after their last session ends
+    pub async fn user_stop_delay(&self) -> zbus::Result<Option<Duration>> {
+        Ok(self.user_stop_delay_USec().await?.to_duration())
+    }
+
+    /// How long a session may be idle before it is stopped
+    pub async fn stop_idle_session_delay(&self) -> zbus::Result<Option<Duration>> {
+        Ok(self.stop_idle_session_USec().await?.to_duration())
+    }
+}
+
+impl ManagerProxyBlocking<'_> {
+    /// How long a delay inhibitor may hold off sleep or shutdown
+    pub fn inhibit_delay_max(&self) -> zbus::Result<Option<Duration>> {
+        Ok(self.inhibit_delay_max_USec()?.to_duration())
+    }
+
+    /// How long lid switches are ignored after boot or resume
+    pub fn holdoff_timeout(&self) -> zbus::Result<Option<Duration>> {
+        Ok(self.holdoff_timeout_USec()?.to_duration())
+    }
+
+    /// How long the system is idle before `IdleAction` is taken
+    pub fn idle_action_delay(&self) -> zbus::Result<Option<Duration>> {
+        Ok(self.idle_action_USec()?.to_duration())
+    }
+
+    /// How long a user's service manager is kept after their last session ends
+    pub fn user_stop_delay(&self) -> zbus::Result<Option<Duration>> {
+        Ok(self.user_stop_delay_USec()?.to_duration())
+    }
+
+    /// How long a session may be idle before it is stopped
+    pub fn stop_idle_session_delay(&self) -> zbus::Result<Option<Duration>> {
+        Ok(self.stop_idle_session_USec()?.to_duration())
+    }
+}
@@ -2,12 +2,21 @@
 
 #![allow(non_snake_case)]
 
-use crate::SomePath;
+use std::collections::HashMap;
+
+use crate::{
+    session::{SessionClass, SessionType},
+    MonotonicStamp, RealtimeStamp, SomePath,
+};
 use zbus::{dbus_proxy, zvariant};
 
 use super::{
-    types::{Inhibitor, IsSupported, ScheduledShutdown},
-    InhibitType, SessionInfo, UserInfo,
+    types::{
+        BootLoaderMenu, HandleAction, InhibitTypes, Inhibitor, InhibitorLock, IsSupported,
+        MaintenanceTime, Mode, PowerFlags, ScheduledShutdown, ShutdownKind, SleepOperation,
+        TimeSpan,
+    },
+    CreatedSession, SessionInfo, SessionInfoEx, UserInfo,
 };
 
 #[dbus_proxy(
@@ -65,6 +74,10 @@ trait Manager {
     #[inline]
     fn can_reboot_to_firmware_setup(&self) -> zbus::Result<IsSupported>;
 
+    /// CanSleep method
+    #[inline]
+    fn can_sleep(&self) -> zbus::Result<IsSupported>;
+
     /// CanSuspend method
     #[inline]
     fn can_suspend(&self) -> zbus::Result<IsSupported>;
@@ -77,22 +90,27 @@ trait Manager {
     #[inline]
     fn cancel_scheduled_shutdown(&self) -> zbus::Result<bool>;
 
-    // /// CreateSession method
-    // #[inline]
-    // fn create_session(
-    //     &self,
-    //     session: SessionCreate,
-    //     properties: &[(&str, zbus::zvariant::Value<'_>)],
-    // ) -> zbus::Result<(
-    //     String,
-    //     zvariant::OwnedObjectPath,
-    //     String,
-    //     std::os::unix::io::RawFd,
-    //     u32,
-    //     String,
-    //     u32,
-    //     bool,
-    // )>;
+    /// CreateSession method. [`ManagerProxy::create_session_with`] takes a
+    /// [`SessionCreateRequest`](super::SessionCreateRequest) instead.
+    #[allow(clippy::too_many_arguments)]
+    #[inline]
+    fn create_session(
+        &self,
+        uid: u32,
+        pid: u32,
+        service: &str,
+        type_: SessionType,
+        class: SessionClass,
+        desktop: &str,
+        seat_id: &str,
+        vtnr: u32,
+        tty: &str,
+        display: &str,
+        remote: bool,
+        remote_user: &str,
+        remote_host: &str,
+        properties: &[(&str, zvariant::Value<'_>)],
+    ) -> zbus::Result<CreatedSession>;
 
     /// FlushDevices method
     #[inline]
@@ -122,23 +140,35 @@ trait Manager {
     #[inline]
     fn halt(&self, interactive: bool) -> zbus::Result<()>;
 
+    /// HaltWithFlags method
+    #[inline]
+    fn halt_with_flags(&self, flags: PowerFlags) -> zbus::Result<()>;
+
     /// Hibernate method
     #[inline]
     fn hibernate(&self, interactive: bool) -> zbus::Result<()>;
 
+    /// HibernateWithFlags method
+    #[inline]
+    fn hibernate_with_flags(&self, flags: PowerFlags) -> zbus::Result<()>;
+
     /// HybridSleep method
     #[inline]
     fn hybrid_sleep(&self, interactive: bool) -> zbus::Result<()>;
 
-    /// Inhibit method
+    /// HybridSleepWithFlags method
+    #[inline]
+    fn hybrid_sleep_with_flags(&self, flags: PowerFlags) -> zbus::Result<()>;
+
+    /// Inhibit method. The returned lock is held until it is dropped or released.
     #[inline]
     fn inhibit(
         &self,
-        what: InhibitType,
+        what: InhibitTypes,
         who: &str,
         why: &str,
-        mode: &str,
-    ) -> zbus::Result<std::os::unix::io::RawFd>;
+        mode: Mode,
+    ) -> zbus::Result<InhibitorLock>;
 
     /// KillSession method
     #[inline]
@@ -160,6 +190,10 @@ trait Manager {
     #[inline]
     fn list_sessions(&self) -> zbus::Result<Vec<SessionInfo>>;
 
+    /// ListSessionsEx method
+    #[inline]
+    fn list_sessions_ex(&self) -> zbus::Result<Vec<SessionInfoEx>>;
+
     /// ListUsers method
     #[inline]
     fn list_users(&self) -> zbus::Result<Vec<UserInfo>>;
@@ -176,17 +210,25 @@ trait Manager {
     #[inline]
     fn power_off(&self, interactive: bool) -> zbus::Result<()>;
 
+    /// PowerOffWithFlags method
+    #[inline]
+    fn power_off_with_flags(&self, flags: PowerFlags) -> zbus::Result<()>;
+
     /// Reboot method
     #[inline]
     fn reboot(&self, interactive: bool) -> zbus::Result<()>;
 
+    /// RebootWithFlags method
+    #[inline]
+    fn reboot_with_flags(&self, flags: PowerFlags) -> zbus::Result<()>;
+
     /// ReleaseSession method
     #[inline]
     fn release_session(&self, session_id: &str) -> zbus::Result<()>;
 
-    /// ScheduleShutdown method
+    /// ScheduleShutdown method. `usec` is the realtime in microseconds to shut down at.
     #[inline]
-    fn schedule_shutdown(&self, shutdown: ScheduledShutdown) -> zbus::Result<()>;
+    fn schedule_shutdown(&self, type_: ShutdownKind, usec: u64) -> zbus::Result<()>;
 
     /// SetRebootParameter method
     #[inline]
@@ -198,7 +240,7 @@ trait Manager {
 
     /// SetRebootToBootLoaderMenu method
     #[inline]
-    fn set_reboot_to_boot_loader_menu(&self, timeout: u64) -> zbus::Result<()>;
+    fn set_reboot_to_boot_loader_menu(&self, timeout: BootLoaderMenu) -> zbus::Result<()>;
 
     /// SetRebootToFirmwareSetup method
     #[inline]
@@ -212,14 +254,26 @@ trait Manager {
     #[inline]
     fn set_wall_message(&self, wall_message: &str, enable: bool) -> zbus::Result<()>;
 
+    /// Sleep method
+    #[inline]
+    fn sleep(&self, flags: PowerFlags) -> zbus::Result<()>;
+
     /// Suspend method
     #[inline]
     fn suspend(&self, interactive: bool) -> zbus::Result<()>;
 
+    /// SuspendWithFlags method
+    #[inline]
+    fn suspend_with_flags(&self, flags: PowerFlags) -> zbus::Result<()>;
+
     /// SuspendThenHibernate method
     #[inline]
     fn suspend_then_hibernate(&self, interactive: bool) -> zbus::Result<()>;
 
+    /// SuspendThenHibernateWithFlags method
+    #[inline]
+    fn suspend_then_hibernate_with_flags(&self, flags: PowerFlags) -> zbus::Result<()>;
+
     /// TerminateSeat method
     #[inline]
     fn terminate_seat(&self, seat_id: &str) -> zbus::Result<()>;
@@ -245,11 +299,29 @@ trait Manager {
     #[inline]
     fn prepare_for_shutdown(&self, start: bool) -> zbus::Result<()>;
 
+    /// PrepareForShutdownWithMetadata signal
+    #[dbus_proxy(signal)]
+    #[inline]
+    fn prepare_for_shutdown_with_metadata(
+        &self,
+        start: bool,
+        metadata: HashMap<String, zvariant::OwnedValue>,
+    ) -> zbus::Result<()>;
+
     /// PrepareForSleep signal
     #[dbus_proxy(signal)]
     #[inline]
     fn prepare_for_sleep(&self, start: bool) -> zbus::Result<()>;
 
+    /// SecureAttentionKey signal
+    #[dbus_proxy(signal)]
+    #[inline]
+    fn secure_attention_key(
+        &self,
+        seat_id: &str,
+        object_path: zvariant::OwnedObjectPath,
+    ) -> zbus::Result<()>;
+
     /// SeatNew signal
     #[dbus_proxy(signal)]
     #[inline]
@@ -295,7 +367,7 @@ trait Manager {
     /// BlockInhibited property
     #[dbus_proxy(property)]
     #[inline]
-    fn block_inhibited(&self) -> zbus::Result<String>;
+    fn block_inhibited(&self) -> zbus::Result<InhibitTypes>;
 
     /// BootLoaderEntries property
     #[dbus_proxy(property)]
@@ -305,7 +377,12 @@ trait Manager {
     /// DelayInhibited property
     #[dbus_proxy(property)]
     #[inline]
-    fn delay_inhibited(&self) -> zbus::Result<String>;
+    fn delay_inhibited(&self) -> zbus::Result<InhibitTypes>;
+
+    /// DesignatedMaintenanceTime property
+    #[dbus_proxy(property)]
+    #[inline]
+    fn designated_maintenance_time(&self) -> zbus::Result<MaintenanceTime>;
 
     /// Docked property
     #[dbus_proxy(property)]
@@ -324,47 +401,77 @@ trait Manager {
     /// HandleHibernateKey property
     #[dbus_proxy(property)]
     #[inline]
-    fn handle_hibernate_key(&self) -> zbus::Result<String>;
+    fn handle_hibernate_key(&self) -> zbus::Result<HandleAction>;
+
+    /// HandleHibernateKeyLongPress property
+    #[dbus_proxy(property)]
+    #[inline]
+    fn handle_hibernate_key_long_press(&self) -> zbus::Result<HandleAction>;
 
     /// HandleLidSwitch property
     #[dbus_proxy(property)]
     #[inline]
-    fn handle_lid_switch(&self) -> zbus::Result<String>;
+    fn handle_lid_switch(&self) -> zbus::Result<HandleAction>;
 
     /// HandleLidSwitchDocked property
     #[dbus_proxy(property)]
     #[inline]
-    fn handle_lid_switch_docked(&self) -> zbus::Result<String>;
+    fn handle_lid_switch_docked(&self) -> zbus::Result<HandleAction>;
 
     /// HandleLidSwitchExternalPower property
     #[dbus_proxy(property)]
     #[inline]
-    fn handle_lid_switch_external_power(&self) -> zbus::Result<String>;
+    fn handle_lid_switch_external_power(&self) -> zbus::Result<HandleAction>;
 
     /// HandlePowerKey property
     #[dbus_proxy(property)]
     #[inline]
-    fn handle_power_key(&self) -> zbus::Result<String>;
+    fn handle_power_key(&self) -> zbus::Result<HandleAction>;
+
+    /// HandlePowerKeyLongPress property
+    #[dbus_proxy(property)]
+    #[inline]
+    fn handle_power_key_long_press(&self) -> zbus::Result<HandleAction>;
+
+    /// HandleRebootKey property
+    #[dbus_proxy(property)]
+    #[inline]
+    fn handle_reboot_key(&self) -> zbus::Result<HandleAction>;
+
+    /// HandleRebootKeyLongPress property
+    #[dbus_proxy(property)]
+    #[inline]
+    fn handle_reboot_key_long_press(&self) -> zbus::Result<HandleAction>;
+
+    /// HandleSecureAttentionKey property
+    #[dbus_proxy(property)]
+    #[inline]
+    fn handle_secure_attention_key(&self) -> zbus::Result<HandleAction>;
 
     /// HandleSuspendKey property
     #[dbus_proxy(property)]
     #[inline]
-    fn handle_suspend_key(&self) -> zbus::Result<String>;
+    fn handle_suspend_key(&self) -> zbus::Result<HandleAction>;
+
+    /// HandleSuspendKeyLongPress property
+    #[dbus_proxy(property)]
+    #[inline]
+    fn handle_suspend_key_long_press(&self) -> zbus::Result<HandleAction>;
 
     /// HoldoffTimeoutUSec property
     #[dbus_proxy(property)]
     #[inline]
-    fn holdoff_timeout_USec(&self) -> zbus::Result<u64>;
+    fn holdoff_timeout_USec(&self) -> zbus::Result<TimeSpan>;
 
     /// IdleAction property
     #[dbus_proxy(property)]
     #[inline]
-    fn idle_action(&self) -> zbus::Result<String>;
+    fn idle_action(&self) -> zbus::Result<HandleAction>;
 
     /// IdleActionUSec property
     #[dbus_proxy(property)]
     #[inline]
-    fn idle_action_USec(&self) -> zbus::Result<u64>;
+    fn idle_action_USec(&self) -> zbus::Result<TimeSpan>;
 
     /// IdleHint property
     #[dbus_proxy(property)]
@@ -374,17 +481,17 @@ trait Manager {
     /// IdleSinceHint property
     #[dbus_proxy(property)]
     #[inline]
-    fn idle_since_hint(&self) -> zbus::Result<u64>;
+    fn idle_since_hint(&self) -> zbus::Result<RealtimeStamp>;
 
     /// IdleSinceHintMonotonic property
     #[dbus_proxy(property)]
     #[inline]
-    fn idle_since_hint_monotonic(&self) -> zbus::Result<u64>;
+    fn idle_since_hint_monotonic(&self) -> zbus::Result<MonotonicStamp>;
 
     /// InhibitDelayMaxUSec property
     #[dbus_proxy(property)]
     #[inline]
-    fn inhibit_delay_max_USec(&self) -> zbus::Result<u64>;
+    fn inhibit_delay_max_USec(&self) -> zbus::Result<TimeSpan>;
 
     /// InhibitorsMax property
     #[dbus_proxy(property)]
@@ -412,7 +519,16 @@ trait Manager {
     fn lid_closed(&self) -> zbus::Result<bool>;
 
     /// NAutoVTs property
-    #[dbus_proxy(property)]
+    ///
+    /// How many virtual terminals get a getty started on demand when switched to. Zero turns
+    /// this off.
+    #[dbus_proxy(property, name = "NAutoVTs")]
+    #[inline]
+    fn n_auto_vts(&self) -> zbus::Result<u32>;
+
+    /// NAutoVTs property, see [`n_auto_vts`](Self::n_auto_vts)
+    #[deprecated(note = "use `n_auto_vts`")]
+    #[dbus_proxy(property, name = "NAutoVTs")]
     #[inline]
     fn NAuto_VTs(&self) -> zbus::Result<u32>;
 
@@ -454,7 +570,7 @@ trait Manager {
     /// RebootToBootLoaderMenu property
     #[dbus_proxy(property)]
     #[inline]
-    fn reboot_to_boot_loader_menu(&self) -> zbus::Result<u64>;
+    fn reboot_to_boot_loader_menu(&self) -> zbus::Result<BootLoaderMenu>;
 
     /// RebootToFirmwareSetup property
     #[dbus_proxy(property)]
@@ -486,10 +602,20 @@ trait Manager {
     #[inline]
     fn sessions_max(&self) -> zbus::Result<u64>;
 
+    /// SleepOperation property
+    #[dbus_proxy(property)]
+    #[inline]
+    fn sleep_operation(&self) -> zbus::Result<SleepOperation>;
+
+    /// StopIdleSessionUSec property
+    #[dbus_proxy(property)]
+    #[inline]
+    fn stop_idle_session_USec(&self) -> zbus::Result<TimeSpan>;
+
     /// UserStopDelayUSec property
     #[dbus_proxy(property)]
     #[inline]
-    fn user_stop_delay_USec(&self) -> zbus::Result<u64>;
+    fn user_stop_delay_USec(&self) -> zbus::Result<TimeSpan>;
 
     /// WallMessage property
     #[dbus_proxy(property)]
@@ -0,0 +1,98 @@
+//! Finding out who holds an inhibitor lock
+//!
+//! logind only reports the uid and pid of an inhibitor's owner. The helpers here turn them
+//! into a user name and command line, as `systemd-inhibit --list` shows them.
+
+use std::{
+    ffi::CStr,
+    fs, io,
+    mem::MaybeUninit,
+    path::{Path, PathBuf},
+    ptr,
+};
+
+use super::{Inhibitor, ManagerProxy, ManagerProxyBlocking, UserInfo};
+
+/// Where the proc filesystem is normally mounted
+const PROC_ROOT: &str = "/proc";
+
+impl Inhibitor {
+    /// The command line of the process that took the lock, read from `/proc`
+    pub fn command_line(&self) -> io::Result<Vec<String>> {
+        self.command_line_in(PROC_ROOT)
+    }
+
+    /// The command line of the process that took the lock, read from the proc filesystem
+    /// mounted at `proc_root`. It is empty for processes without one, such as zombies.
+    pub fn command_line_in(&self, proc_root: impl AsRef<Path>) -> io::Result<Vec<String>> {
+        let path: PathBuf = [
+            proc_root.as_ref(),
+            Path::new(&self.process_id().to_string()),
+            Path::new("cmdline"),
+        ]
+        .iter()
+        .collect();
+        let cmdline = fs::read(path)?;
+        Ok(cmdline
+            .split(|&b| b == 0)
+            .filter(|arg| !arg.is_empty())
+            .map(|arg| String::from_utf8_lossy(arg).into_owned())
+            .collect())
+    }
+}
+
+/// The name of `uid` among logind's users, or else in the user database
+fn user_name(users: Vec<UserInfo>, uid: u32) -> io::Result<Option<String>> {
+    match users.into_iter().find(|user| user.uid() == uid) {
+        Some(user) => Ok(Some(user.name().to_string())),
+        None => passwd_name(uid),
+    }
+}
+
+/// Look up `uid` with `getpwuid_r`, for root and system users that logind has no user for
+pub(super) fn passwd_name(uid: u32) -> io::Result<Option<String>> {
+    let mut buf = vec![0u8; 1024];
+    loop {
+        let mut passwd = MaybeUninit::<libc::passwd>::uninit();
+        let mut found = ptr::null_mut();
+        // SAFETY: `passwd` and `buf` are valid for writes, and `buf` for its whole length
+        let err = unsafe {
+            libc::getpwuid_r(
+                uid,
+                passwd.as_mut_ptr(),
+                buf.as_mut_ptr().cast(),
+                buf.len(),
+                &mut found,
+            )
+        };
+        match err {
+            0 if found.is_null() => return Ok(None),
+            // SAFETY: `found` points to `passwd`, filled in with strings that live in `buf`
+            0 => {
+                let name = unsafe { CStr::from_ptr((*found).pw_name) };
+                return Ok(Some(name.to_string_lossy().into_owned()));
+            }
+            libc::ERANGE => buf.resize(buf.len() * 2, 0),
+            // Some user database backends report an unknown uid as one of these
+            libc::ENOENT | libc::ESRCH | libc::EBADF | libc::EPERM => return Ok(None),
+            err => return Err(io::Error::from_raw_os_error(err)),
+        }
+    }
+}
+
+impl ManagerProxy<'_> {
+    /// The name of the user that took `inhibitor`
+    ///
+    /// Users logind knows about are looked up with `ListUsers`, and others such as root and
+    /// system daemons in the user database. `None` for a uid that has no name in either.
+    pub async fn inhibitor_user_name(&self, inhibitor: &Inhibitor) -> zbus::Result<Option<String>> {
+        Ok(user_name(self.list_users().await?, inhibitor.user_id())?)
+    }
+}
+
+impl ManagerProxyBlocking<'_> {
+    /// The name of the user that took `inhibitor`, see [`ManagerProxy::inhibitor_user_name`]
+    pub fn inhibitor_user_name(&self, inhibitor: &Inhibitor) -> zbus::Result<Option<String>> {
+        Ok(user_name(self.list_users()?, inhibitor.user_id())?)
+    }
+}
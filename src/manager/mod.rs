@@ -1,8 +1,21 @@
+mod capabilities;
+mod create;
+mod delay;
+mod diagnostics;
+mod durations;
 mod generated;
+mod inhibitor;
+mod power;
+mod sessions;
+mod shutdown;
 #[cfg(test)]
 mod tests;
 
 mod types;
 
+pub use capabilities::*;
+pub use create::*;
+pub use delay::*;
+pub use diagnostics::*;
 pub use generated::*;
 pub use types::*;
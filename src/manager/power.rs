@@ -0,0 +1,121 @@
+//! Power actions by value, rather than by one method per action
+
+use zbus::MethodFlags;
+
+use super::{IsSupported, ManagerProxy, ManagerProxyBlocking, PowerAction, PowerFlags};
+
+impl ManagerProxy<'_> {
+    /// Call the `Can*` method for `action`
+    pub async fn can(&self, action: PowerAction) -> zbus::Result<IsSupported> {
+        match action {
+            PowerAction::PowerOff => self.can_power_off().await,
+            PowerAction::Reboot => self.can_reboot().await,
+            PowerAction::Halt => self.can_halt().await,
+            PowerAction::Suspend => self.can_suspend().await,
+            PowerAction::Hibernate => self.can_hibernate().await,
+            PowerAction::HybridSleep => self.can_hybrid_sleep().await,
+            PowerAction::SuspendThenHibernate => self.can_suspend_then_hibernate().await,
+            PowerAction::Sleep => self.can_sleep().await,
+        }
+    }
+
+    /// Carry out `action`. With `interactive`, polkit may ask the user to authenticate.
+    pub async fn perform(&self, action: PowerAction, interactive: bool) -> zbus::Result<()> {
+        match action {
+            PowerAction::PowerOff => self.power_off(interactive).await,
+            PowerAction::Reboot => self.reboot(interactive).await,
+            PowerAction::Halt => self.halt(interactive).await,
+            PowerAction::Suspend => self.suspend(interactive).await,
+            PowerAction::Hibernate => self.hibernate(interactive).await,
+            PowerAction::HybridSleep => self.hybrid_sleep(interactive).await,
+            PowerAction::SuspendThenHibernate => self.suspend_then_hibernate(interactive).await,
+            PowerAction::Sleep if interactive => {
+                // `Sleep` has no `interactive` argument, the message header says it instead
+                self.inner()
+                    .call_with_flags::<_, _, ()>(
+                        "Sleep",
+                        MethodFlags::AllowInteractiveAuth.into(),
+                        &(PowerFlags::empty(),),
+                    )
+                    .await?;
+                Ok(())
+            }
+            PowerAction::Sleep => self.sleep(PowerFlags::empty()).await,
+        }
+    }
+
+    /// Carry out `action` with the `*WithFlags` method, which logind has since systemd 246.
+    /// `Sleep` always takes flags.
+    pub async fn perform_with_flags(
+        &self,
+        action: PowerAction,
+        flags: PowerFlags,
+    ) -> zbus::Result<()> {
+        match action {
+            PowerAction::PowerOff => self.power_off_with_flags(flags).await,
+            PowerAction::Reboot => self.reboot_with_flags(flags).await,
+            PowerAction::Halt => self.halt_with_flags(flags).await,
+            PowerAction::Suspend => self.suspend_with_flags(flags).await,
+            PowerAction::Hibernate => self.hibernate_with_flags(flags).await,
+            PowerAction::HybridSleep => self.hybrid_sleep_with_flags(flags).await,
+            PowerAction::SuspendThenHibernate => {
+                self.suspend_then_hibernate_with_flags(flags).await
+            }
+            PowerAction::Sleep => self.sleep(flags).await,
+        }
+    }
+}
+
+impl ManagerProxyBlocking<'_> {
+    /// Call the `Can*` method for `action`
+    pub fn can(&self, action: PowerAction) -> zbus::Result<IsSupported> {
+        match action {
+            PowerAction::PowerOff => self.can_power_off(),
+            PowerAction::Reboot => self.can_reboot(),
+            PowerAction::Halt => self.can_halt(),
+            PowerAction::Suspend => self.can_suspend(),
+            PowerAction::Hibernate => self.can_hibernate(),
+            PowerAction::HybridSleep => self.can_hybrid_sleep(),
+            PowerAction::SuspendThenHibernate => self.can_suspend_then_hibernate(),
+            PowerAction::Sleep => self.can_sleep(),
+        }
+    }
+
+    /// Carry out `action`. With `interactive`, polkit may ask the user to authenticate.
+    pub fn perform(&self, action: PowerAction, interactive: bool) -> zbus::Result<()> {
+        match action {
+            PowerAction::PowerOff => self.power_off(interactive),
+            PowerAction::Reboot => self.reboot(interactive),
+            PowerAction::Halt => self.halt(interactive),
+            PowerAction::Suspend => self.suspend(interactive),
+            PowerAction::Hibernate => self.hibernate(interactive),
+            PowerAction::HybridSleep => self.hybrid_sleep(interactive),
+            PowerAction::SuspendThenHibernate => self.suspend_then_hibernate(interactive),
+            PowerAction::Sleep if interactive => {
+                // `Sleep` has no `interactive` argument, the message header says it instead
+                self.inner().call_with_flags::<_, _, ()>(
+                    "Sleep",
+                    MethodFlags::AllowInteractiveAuth.into(),
+                    &(PowerFlags::empty(),),
+                )?;
+                Ok(())
+            }
+            PowerAction::Sleep => self.sleep(PowerFlags::empty()),
+        }
+    }
+
+    /// Carry out `action` with the `*WithFlags` method, which logind has since systemd 246.
+    /// `Sleep` always takes flags.
+    pub fn perform_with_flags(&self, action: PowerAction, flags: PowerFlags) -> zbus::Result<()> {
+        match action {
+            PowerAction::PowerOff => self.power_off_with_flags(flags),
+            PowerAction::Reboot => self.reboot_with_flags(flags),
+            PowerAction::Halt => self.halt_with_flags(flags),
+            PowerAction::Suspend => self.suspend_with_flags(flags),
+            PowerAction::Hibernate => self.hibernate_with_flags(flags),
+            PowerAction::HybridSleep => self.hybrid_sleep_with_flags(flags),
+            PowerAction::SuspendThenHibernate => self.suspend_then_hibernate_with_flags(flags),
+            PowerAction::Sleep => self.sleep(flags),
+        }
+    }
+}
@@ -0,0 +1,104 @@
+//! Listing sessions with their details, on any logind version
+
+use futures_util::future::join_all;
+use zbus::{
+    fdo::{self, PropertiesProxy},
+    names::InterfaceName,
+    zvariant::OwnedObjectPath,
+};
+
+use crate::error::{Error, Result};
+
+use super::{ManagerProxy, ManagerProxyBlocking, SessionInfo, SessionInfoEx};
+
+const SESSION_INTERFACE: &str = "org.freedesktop.login1.Session";
+
+/// `None` for an object that went away
+fn unless_gone<T>(result: zbus::Result<T>) -> zbus::Result<Option<T>> {
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(zbus::Error::FDO(e)) if matches!(*e, fdo::Error::UnknownObject(_)) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+impl ManagerProxy<'_> {
+    /// List the sessions with `ListSessionsEx`, and read the type of each
+    ///
+    /// logind versions without `ListSessionsEx` get `ListSessions` instead, followed by one
+    /// `GetAll` per session. The reads are all sent at once, and sessions that end before
+    /// theirs are left out.
+    pub async fn list_sessions_extended(&self) -> Result<Vec<SessionInfoEx>> {
+        let sessions = match self.list_sessions_ex().await.map_err(Error::from) {
+            Ok(sessions) => join_all(sessions.into_iter().map(|s| self.with_type(s))).await,
+            Err(Error::UnknownMethod(_)) => {
+                let sessions = self.list_sessions().await?;
+                join_all(sessions.into_iter().map(|s| self.session_details(s))).await
+            }
+            Err(e) => return Err(e),
+        };
+        let sessions = sessions
+            .into_iter()
+            .filter_map(zbus::Result::transpose)
+            .collect::<zbus::Result<_>>()?;
+        Ok(sessions)
+    }
+
+    /// `None` when the session is gone
+    pub(super) async fn session_details(
+        &self,
+        info: SessionInfo,
+    ) -> zbus::Result<Option<SessionInfoEx>> {
+        unless_gone(self.read_session_details(info).await)
+    }
+
+    async fn read_session_details(&self, info: SessionInfo) -> zbus::Result<SessionInfoEx> {
+        let properties = self
+            .session_properties(info.path())
+            .await?
+            .get_all(InterfaceName::from_static_str_unchecked(SESSION_INTERFACE))
+            .await?;
+        SessionInfoEx::from_properties(info, properties)
+    }
+
+    /// Add the `Type` that `ListSessionsEx` leaves out, `None` when the session is gone
+    pub(super) async fn with_type(
+        &self,
+        mut session: SessionInfoEx,
+    ) -> zbus::Result<Option<SessionInfoEx>> {
+        let read = async {
+            self.session_properties(session.path())
+                .await?
+                .get(
+                    InterfaceName::from_static_str_unchecked(SESSION_INTERFACE),
+                    "Type",
+                )
+                .await
+                .map_err(zbus::Error::from)
+        };
+        let Some(session_type) = unless_gone(read.await)? else {
+            return Ok(None);
+        };
+        session.set_session_type(session_type.try_into()?);
+        Ok(Some(session))
+    }
+
+    async fn session_properties(
+        &self,
+        path: &OwnedObjectPath,
+    ) -> zbus::Result<PropertiesProxy<'static>> {
+        PropertiesProxy::builder(self.inner().connection())
+            .destination(self.inner().destination().to_owned())?
+            .path(path.clone())?
+            .build()
+            .await
+    }
+}
+
+impl ManagerProxyBlocking<'_> {
+    /// List the sessions with `ListSessionsEx`, see [`ManagerProxy::list_sessions_extended`]
+    pub fn list_sessions_extended(&self) -> Result<Vec<SessionInfoEx>> {
+        let manager = ManagerProxy::from(self.inner().inner().clone());
+        zbus::block_on(manager.list_sessions_extended())
+    }
+}
@@ -0,0 +1,51 @@
+//! Scheduling shutdowns by time rather than raw microseconds
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use super::{ManagerProxy, ManagerProxyBlocking, ShutdownKind};
+
+/// Microseconds since the epoch, as `ScheduleShutdown` takes them. Times before the epoch
+/// are clamped to it.
+fn realtime_usec(time: SystemTime) -> u64 {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    since_epoch.as_micros().try_into().unwrap_or(u64::MAX)
+}
+
+/// Microseconds since the epoch once `delay` has passed, clamped like [`realtime_usec`]
+fn realtime_usec_in(delay: Duration) -> u64 {
+    SystemTime::now()
+        .checked_add(delay)
+        .map_or(u64::MAX, realtime_usec)
+}
+
+impl ManagerProxy<'_> {
+    /// Schedule a shutdown of `kind` at `time`, replacing any already scheduled
+    pub async fn schedule_shutdown_at(
+        &self,
+        kind: ShutdownKind,
+        time: SystemTime,
+    ) -> zbus::Result<()> {
+        self.schedule_shutdown(kind, realtime_usec(time)).await
+    }
+
+    /// Schedule a shutdown of `kind` once `delay` has passed
+    pub async fn schedule_shutdown_in(
+        &self,
+        kind: ShutdownKind,
+        delay: Duration,
+    ) -> zbus::Result<()> {
+        self.schedule_shutdown(kind, realtime_usec_in(delay)).await
+    }
+}
+
+impl ManagerProxyBlocking<'_> {
+    /// Schedule a shutdown of `kind` at `time`, replacing any already scheduled
+    pub fn schedule_shutdown_at(&self, kind: ShutdownKind, time: SystemTime) -> zbus::Result<()> {
+        self.schedule_shutdown(kind, realtime_usec(time))
+    }
+
+    /// Schedule a shutdown of `kind` once `delay` has passed
+    pub fn schedule_shutdown_in(&self, kind: ShutdownKind, delay: Duration) -> zbus::Result<()> {
+        self.schedule_shutdown(kind, realtime_usec_in(delay))
+    }
+}
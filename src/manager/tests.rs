@@ -1,9 +1,30 @@
-use crate::manager::{InhibitType, IsSupported, ManagerProxy, ManagerProxyBlocking, Mode};
+use std::os::unix::io::{AsRawFd, OwnedFd};
+use std::{
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::manager::{
+    BootLoaderMenu, DelayEvent, HandleAction, InhibitType, InhibitTypes, IsSupported, ManagerProxy,
+    ManagerProxyBlocking, Mode, PowerAction, PowerDiagnosticsBlocking, PowerFlags,
+    SessionCreateRequest, ShutdownGuardBlocking, ShutdownKind, SleepGuard, SleepMode, TimeSpan,
+};
+use crate::session::{SessionClass, SessionProxyBlocking, SessionType};
+use crate::{
+    testing::{self, FakeSession},
+    Error,
+};
 use futures_lite::future;
+use std::str::FromStr;
+use zbus::{
+    zvariant::{OwnedValue, Type},
+    CacheProperties,
+};
 
 #[test]
 fn timestamps() {
-    let connection = zbus::blocking::Connection::system().unwrap();
+    let fake = future::block_on(testing::desktop());
+    let connection = fake.blocking_connection();
     let manager = ManagerProxyBlocking::new(&connection).unwrap();
 
     assert!(manager.can_suspend().is_ok());
@@ -11,7 +32,8 @@ fn timestamps() {
 
 #[test]
 fn properties() {
-    let connection = zbus::blocking::Connection::system().unwrap();
+    let fake = future::block_on(testing::desktop());
+    let connection = fake.blocking_connection();
     let manager = ManagerProxyBlocking::new(&connection).unwrap();
 
     assert!(manager.block_inhibited().is_ok());
@@ -36,7 +58,10 @@ fn properties() {
     assert!(manager.kill_only_users().is_ok());
     assert!(manager.kill_user_processes().is_ok());
     assert!(manager.lid_closed().is_ok());
-    assert!(manager.NAuto_VTs().is_ok());
+    assert!(manager.n_auto_vts().is_ok());
+    #[allow(deprecated)]
+    let n_auto_vts = manager.NAuto_VTs().unwrap();
+    assert_eq!(n_auto_vts, manager.n_auto_vts().unwrap());
     assert!(manager.NCurrent_inhibitors().is_ok());
     assert!(manager.NCurrent_sessions().is_ok());
     assert!(manager.on_external_power().is_ok());
@@ -69,7 +94,8 @@ fn properties() {
 #[test]
 fn timestamps_async() {
     future::block_on(async {
-        let connection = zbus::Connection::system().await.unwrap();
+        let fake = testing::desktop().await;
+        let connection = fake.connection().clone();
         let manager = ManagerProxy::new(&connection).await.unwrap();
 
         assert!(manager.can_suspend().await.is_ok());
@@ -79,7 +105,8 @@ fn timestamps_async() {
 #[test]
 fn properties_async() {
     future::block_on(async {
-        let connection = zbus::Connection::system().await.unwrap();
+        let fake = testing::desktop().await;
+        let connection = fake.connection().clone();
         let manager = ManagerProxy::new(&connection).await.unwrap();
 
         assert!(manager.block_inhibited().await.is_ok());
@@ -104,7 +131,7 @@ fn properties_async() {
         assert!(manager.kill_only_users().await.is_ok());
         assert!(manager.kill_user_processes().await.is_ok());
         assert!(manager.lid_closed().await.is_ok());
-        assert!(manager.NAuto_VTs().await.is_ok());
+        assert!(manager.n_auto_vts().await.is_ok());
         assert!(manager.NCurrent_inhibitors().await.is_ok());
         assert!(manager.NCurrent_sessions().await.is_ok());
         assert!(manager.on_external_power().await.is_ok());
@@ -137,7 +164,8 @@ fn properties_async() {
 
 #[test]
 fn inhibitors() {
-    let connection = zbus::blocking::Connection::system().unwrap();
+    let fake = future::block_on(testing::desktop());
+    let connection = fake.blocking_connection();
     let manager = ManagerProxyBlocking::new(&connection).unwrap();
 
     assert!(manager.can_suspend().is_ok());
@@ -148,10 +176,10 @@ fn inhibitors() {
     assert_eq!(n_inhibitors, inhibitors.unwrap().len() as u64);
 
     let res = manager.inhibit(
-        InhibitType::HandleHibernateKey,
+        InhibitType::HandleHibernateKey.into(),
         "inhibit test",
         "inhibit test",
-        <&str>::from(Mode::Delay),
+        Mode::Delay,
     );
 
     assert!(res.is_err());
@@ -168,7 +196,8 @@ fn inhibitors() {
 
 #[test]
 fn can_do() {
-    let connection = zbus::blocking::Connection::system().unwrap();
+    let fake = future::block_on(testing::desktop());
+    let connection = fake.blocking_connection();
     let manager = ManagerProxyBlocking::new(&connection).unwrap();
 
     let res = manager.can_suspend();
@@ -183,3 +212,764 @@ fn can_do() {
     assert!(res.is_ok());
     assert_eq!(res, zbus::Result::Ok(IsSupported::NA));
 }
+
+fn wait_for_inhibitors(manager: &ManagerProxyBlocking, count: usize) {
+    for _ in 0..100 {
+        if manager.list_inhibitors().unwrap().len() == count {
+            return;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+    panic!("expected {count} inhibitors");
+}
+
+#[test]
+fn inhibitor_lock() {
+    let fake = future::block_on(testing::desktop());
+    let connection = fake.blocking_connection();
+    let manager = ManagerProxyBlocking::new(&connection).unwrap();
+
+    let lock = manager
+        .inhibit(
+            InhibitTypes::new(&[InhibitType::Sleep, InhibitType::Shutdown]),
+            "inhibit test",
+            "inhibit test",
+            Mode::Delay,
+        )
+        .unwrap();
+    // The lock must outlive the reply it arrived in
+    wait_for_inhibitors(&manager, 1);
+    let inhibited = manager.delay_inhibited().unwrap();
+    assert!(inhibited.contains(&InhibitType::Sleep));
+    assert_eq!(inhibited.to_string(), "sleep:shutdown");
+    let inhibitors = fake.inhibitors();
+    assert_eq!(inhibitors[0].who, "inhibit test");
+    assert_eq!(inhibitors[0].mode, Mode::Delay);
+
+    lock.release();
+    wait_for_inhibitors(&manager, 0);
+
+    let lock = manager
+        .inhibit(InhibitType::Idle.into(), "inhibit test", "", Mode::Block)
+        .unwrap();
+    wait_for_inhibitors(&manager, 1);
+    drop(lock);
+    wait_for_inhibitors(&manager, 0);
+}
+
+#[test]
+fn inhibitor_lock_inheritable() {
+    let fake = future::block_on(testing::desktop());
+    let connection = fake.blocking_connection();
+    let manager = ManagerProxyBlocking::new(&connection).unwrap();
+
+    let lock = manager
+        .inhibit(InhibitType::Sleep.into(), "inhibit test", "", Mode::Block)
+        .unwrap();
+    let flags = unsafe { libc::fcntl(lock.as_raw_fd(), libc::F_GETFD) };
+    assert_ne!(flags & libc::FD_CLOEXEC, 0);
+
+    let fd: OwnedFd = lock.into_inheritable_fd().unwrap();
+    let flags = unsafe { libc::fcntl(fd.as_raw_fd(), libc::F_GETFD) };
+    assert_eq!(flags & libc::FD_CLOEXEC, 0);
+    wait_for_inhibitors(&manager, 1);
+
+    drop(fd);
+    wait_for_inhibitors(&manager, 0);
+}
+
+#[test]
+fn sleep_guard() {
+    future::block_on(async {
+        let fake = testing::desktop().await;
+        // Read DelayInhibited back right away rather than waiting for the cache to catch up
+        let manager = ManagerProxy::builder(fake.connection())
+            .cache_properties(CacheProperties::No)
+            .build()
+            .await
+            .unwrap();
+        let mut guard = SleepGuard::new(&manager, "guard test", "saving state")
+            .await
+            .unwrap();
+        assert_eq!(guard.deadline(), Duration::from_secs(5));
+        assert!(guard.is_held());
+        assert_eq!(
+            manager.delay_inhibited().await.unwrap(),
+            InhibitType::Sleep.into()
+        );
+
+        fake.prepare_for_sleep(true).await.unwrap();
+        let mut ran = false;
+        let event = guard.next(|| async { ran = true }).await.unwrap();
+        assert!(ran);
+        match event {
+            DelayEvent::Prepared(report) => assert!(!report.overran()),
+            DelayEvent::Resumed => panic!("expected Prepared"),
+        }
+        assert!(!guard.is_held());
+
+        fake.prepare_for_sleep(false).await.unwrap();
+        let event = guard.next(|| async {}).await.unwrap();
+        assert_eq!(event, DelayEvent::Resumed);
+        assert!(guard.is_held());
+        assert_eq!(fake.inhibitors().len(), 1);
+    })
+}
+
+#[test]
+fn sleep_guard_overrun() {
+    future::block_on(async {
+        let fake = testing::desktop().await;
+        fake.update_manager(|m| m.inhibit_delay_max_usec = 50_000)
+            .await
+            .unwrap();
+        let manager = ManagerProxy::new(fake.connection()).await.unwrap();
+        let mut guard = SleepGuard::new(&manager, "guard test", "").await.unwrap();
+
+        fake.prepare_for_sleep(true).await.unwrap();
+        let event = guard
+            .next(|| async {
+                async_io::Timer::after(Duration::from_secs(10)).await;
+            })
+            .await
+            .unwrap();
+        match event {
+            DelayEvent::Prepared(report) => {
+                assert!(report.overran());
+                assert!(report.elapsed < Duration::from_secs(10));
+            }
+            DelayEvent::Resumed => panic!("expected Prepared"),
+        }
+    })
+}
+
+#[test]
+fn sleep_guard_infinite_delay() {
+    future::block_on(async {
+        let fake = testing::desktop().await;
+        fake.update_manager(|m| m.inhibit_delay_max_usec = u64::MAX)
+            .await
+            .unwrap();
+        let manager = ManagerProxy::new(fake.connection()).await.unwrap();
+        let mut guard = SleepGuard::new(&manager, "guard test", "").await.unwrap();
+        assert_eq!(guard.deadline(), Duration::MAX);
+
+        fake.prepare_for_sleep(true).await.unwrap();
+        let event = guard
+            .next(|| async {
+                async_io::Timer::after(Duration::from_millis(50)).await;
+            })
+            .await
+            .unwrap();
+        match event {
+            DelayEvent::Prepared(report) => {
+                assert!(!report.overran());
+                assert!(report.elapsed >= Duration::from_millis(50));
+            }
+            DelayEvent::Resumed => panic!("expected Prepared"),
+        }
+    })
+}
+
+#[test]
+fn shutdown_guard_blocking() {
+    let fake = future::block_on(testing::desktop());
+    future::block_on(fake.update_manager(|m| m.inhibit_delay_max_usec = 50_000)).unwrap();
+    let connection = fake.blocking_connection();
+    let manager = ManagerProxyBlocking::new(&connection).unwrap();
+    let mut guard = ShutdownGuardBlocking::new(&manager, "guard test", "").unwrap();
+    wait_for_inhibitors(&manager, 1);
+
+    future::block_on(fake.prepare_for_shutdown(true)).unwrap();
+    let event = guard
+        .next(|| thread::sleep(Duration::from_millis(100)))
+        .unwrap();
+    match event {
+        DelayEvent::Prepared(report) => assert!(report.overran()),
+        DelayEvent::Resumed => panic!("expected Prepared"),
+    }
+    wait_for_inhibitors(&manager, 0);
+
+    // Shutdown was cancelled
+    future::block_on(fake.prepare_for_shutdown(false)).unwrap();
+    assert_eq!(guard.next(|| ()).unwrap(), DelayEvent::Resumed);
+    wait_for_inhibitors(&manager, 1);
+}
+
+#[test]
+fn typed_errors() {
+    let fake = future::block_on(testing::desktop());
+    let connection = fake.blocking_connection();
+    let manager = ManagerProxyBlocking::new(&connection).unwrap();
+
+    let err = Error::from(manager.get_session("nope").unwrap_err());
+    assert!(matches!(err, Error::NoSuchSession(_)), "{err:?}");
+    assert_eq!(
+        err.dbus_name(),
+        Some("org.freedesktop.login1.NoSuchSession")
+    );
+
+    let err = Error::from(manager.get_seat("seat9").unwrap_err());
+    assert!(matches!(err, Error::NoSuchSeat(_)), "{err:?}");
+
+    let err = Error::from(manager.get_user(4321).unwrap_err());
+    assert!(matches!(err, Error::NoSuchUser(_)), "{err:?}");
+
+    let err = Error::from(
+        manager
+            .inhibit(InhibitType::Idle.into(), "test", "test", Mode::Delay)
+            .unwrap_err(),
+    );
+    assert!(matches!(err, Error::Zbus(_)), "{err:?}");
+    assert_eq!(
+        err.dbus_name(),
+        Some("org.freedesktop.DBus.Error.InvalidArgs")
+    );
+}
+
+#[test]
+fn unknown_values() {
+    let what = InhibitTypes::from_str("sleep:nap").unwrap();
+    assert_eq!(
+        what,
+        InhibitTypes::new(&[InhibitType::Sleep, InhibitType::Other("nap".to_string())])
+    );
+    assert_eq!(String::from(what), "sleep:nap");
+    assert!(InhibitTypes::from_str("").unwrap().is_empty());
+    assert_eq!(Mode::from("block-weak"), Mode::BlockWeak);
+    assert_eq!(IsSupported::from("maybe").as_str(), "maybe");
+}
+
+#[test]
+fn create_session() {
+    let fake = future::block_on(testing::desktop());
+    let connection = fake.blocking_connection();
+    let manager = ManagerProxyBlocking::new(&connection).unwrap();
+
+    let request = SessionCreateRequest::new(1001, 4321)
+        .service("greeter-test")
+        .session_type(SessionType::Wayland)
+        .class(SessionClass::Greeter)
+        .seat("seat0")
+        .vtnr(1)
+        .tty("tty1")
+        .property("StopOnIdle", false);
+    let session = manager.create_session_with(&request).unwrap();
+    assert_eq!(session.uid(), 1001);
+    assert_eq!(session.seat_id(), "seat0");
+    assert_eq!(session.runtime_path(), "/run/user/1001");
+    assert!(!session.existing());
+    assert_eq!(manager.list_sessions().unwrap().len(), 2);
+
+    let path = manager.get_session(session.session_id()).unwrap();
+    assert_eq!(&path, session.object_path());
+    let created = SessionProxyBlocking::builder(&connection)
+        .path(path)
+        .unwrap()
+        .build()
+        .unwrap();
+    assert_eq!(created.class().unwrap(), SessionClass::Greeter);
+    assert_eq!(created.type_().unwrap(), SessionType::Wayland);
+    assert_eq!(created.leader().unwrap(), 4321);
+
+    // Closing the FIFO ends the session
+    drop(session);
+    for _ in 0..100 {
+        if manager.list_sessions().unwrap().len() == 1 {
+            return;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+    panic!("session was not removed");
+}
+
+#[test]
+fn schedule_shutdown() {
+    let fake = future::block_on(testing::desktop());
+    let connection = fake.blocking_connection();
+    // Read the property back right away rather than waiting for the cache to catch up
+    let manager = ManagerProxyBlocking::builder(&connection)
+        .cache_properties(CacheProperties::No)
+        .build()
+        .unwrap();
+    assert_eq!(manager.scheduled_shutdown().unwrap().into_inner(), None);
+
+    let at = UNIX_EPOCH + Duration::from_secs(2_000_000_000);
+    manager
+        .schedule_shutdown_at(ShutdownKind::DrySoftReboot, at)
+        .unwrap();
+    let scheduled = manager.scheduled_shutdown().unwrap();
+    assert_eq!(scheduled.kind(), Some(&ShutdownKind::DrySoftReboot));
+    assert_eq!(scheduled.time(), Some(at));
+
+    manager
+        .schedule_shutdown_in(ShutdownKind::PowerOff, Duration::from_secs(60))
+        .unwrap();
+    let (kind, time) = manager.scheduled_shutdown().unwrap().into_inner().unwrap();
+    assert_eq!(kind, ShutdownKind::PowerOff);
+    assert!(time > SystemTime::now());
+
+    // Too far off to represent is clamped rather than overflowing
+    manager
+        .schedule_shutdown_in(ShutdownKind::PowerOff, Duration::MAX)
+        .unwrap();
+    let scheduled = manager.scheduled_shutdown().unwrap();
+    assert_eq!(
+        scheduled.time(),
+        Some(UNIX_EPOCH + Duration::from_micros(u64::MAX))
+    );
+
+    assert!(manager
+        .schedule_shutdown(ShutdownKind::Other("nap".to_string()), 0)
+        .is_err());
+    assert!(manager.cancel_scheduled_shutdown().unwrap());
+    assert_eq!(manager.scheduled_shutdown().unwrap().kind(), None);
+}
+
+#[test]
+fn time_spans() {
+    let fake = future::block_on(testing::desktop());
+    future::block_on(fake.update_manager(|m| m.user_stop_delay_usec = u64::MAX)).unwrap();
+    let connection = fake.blocking_connection();
+    let manager = ManagerProxyBlocking::builder(&connection)
+        .cache_properties(CacheProperties::No)
+        .build()
+        .unwrap();
+
+    assert_eq!(
+        manager.holdoff_timeout().unwrap(),
+        Some(Duration::from_secs(30))
+    );
+    assert_eq!(manager.user_stop_delay().unwrap(), None);
+    assert_eq!(
+        manager.idle_action_delay().unwrap(),
+        Some(Duration::from_secs(1800))
+    );
+    assert_eq!(
+        manager.inhibit_delay_max().unwrap(),
+        Some(Duration::from_secs(5))
+    );
+    assert!(manager.user_stop_delay_USec().unwrap().is_infinite());
+    assert_eq!(TimeSpan::from(None), TimeSpan::INFINITY);
+    assert_eq!(TimeSpan::from(Duration::MAX), TimeSpan::INFINITY);
+
+    assert_eq!(
+        manager.reboot_to_boot_loader_menu().unwrap(),
+        BootLoaderMenu::Skip
+    );
+    for menu in [
+        BootLoaderMenu::Timeout(Duration::from_secs(10)),
+        BootLoaderMenu::NoTimeout,
+        BootLoaderMenu::Skip,
+    ] {
+        manager.set_reboot_to_boot_loader_menu(menu).unwrap();
+        assert_eq!(manager.reboot_to_boot_loader_menu().unwrap(), menu);
+    }
+    assert_eq!(
+        BootLoaderMenu::Timeout(Duration::ZERO).as_micros(),
+        BootLoaderMenu::Timeout(Duration::from_micros(1)).as_micros()
+    );
+}
+
+#[test]
+fn handle_actions() {
+    let fake = future::block_on(testing::desktop());
+    future::block_on(fake.update_manager(|m| {
+        m.handle_lid_switch = HandleAction::SuspendThenHibernate;
+        m.idle_action = HandleAction::Other("nap".to_string());
+    }))
+    .unwrap();
+    let connection = fake.blocking_connection();
+    let manager = ManagerProxyBlocking::new(&connection).unwrap();
+
+    assert_eq!(manager.handle_power_key().unwrap(), HandleAction::PowerOff);
+    assert_eq!(
+        manager.handle_lid_switch().unwrap(),
+        HandleAction::SuspendThenHibernate
+    );
+    assert_eq!(
+        manager.idle_action().unwrap(),
+        HandleAction::Other("nap".to_string())
+    );
+    assert_eq!(
+        HandleAction::from("secure-attention-key"),
+        HandleAction::SecureAttentionKey
+    );
+    assert_eq!(HandleAction::HybridSleep.as_str(), "hybrid-sleep");
+}
+
+#[test]
+fn inhibit_types_set() {
+    let mut types = InhibitTypes::from_str("sleep:idle:sleep").unwrap();
+    assert_eq!(types.len(), 2);
+    assert!(types.contains(&InhibitType::Idle));
+    assert!(!types.insert(InhibitType::Sleep));
+    assert!(types.insert(InhibitType::Shutdown));
+    assert!(types.remove(&InhibitType::Idle));
+    assert_eq!(types, InhibitType::Shutdown | InhibitType::Sleep);
+
+    let both = types.clone() & (InhibitType::Sleep | InhibitType::Idle);
+    assert_eq!(both.iter().collect::<Vec<_>>(), [&InhibitType::Sleep]);
+    let all = types | InhibitType::Idle;
+    assert_eq!(all.to_string(), "sleep:shutdown:idle");
+    let collected: InhibitTypes = all
+        .into_iter()
+        .filter(|t| t != &InhibitType::Sleep)
+        .collect();
+    assert_eq!(String::from(collected), "shutdown:idle");
+}
+
+#[test]
+fn inhibitor_owner() {
+    let fake = future::block_on(testing::desktop());
+    for (uid, pid) in [(1000, 4242), (0, 4343)] {
+        future::block_on(fake.add_inhibitor(testing::FakeInhibitor {
+            what: InhibitType::Sleep.into(),
+            who: "player".to_string(),
+            why: "Playing music".to_string(),
+            mode: Mode::Block,
+            uid,
+            pid,
+        }))
+        .unwrap();
+    }
+    let connection = fake.blocking_connection();
+    let manager = ManagerProxyBlocking::new(&connection).unwrap();
+    let inhibitors = manager.list_inhibitors().unwrap();
+    let inhibitor = &inhibitors[0];
+    assert_eq!(inhibitor.who(), "player");
+    assert_eq!(inhibitor.why(), "Playing music");
+    assert_eq!(inhibitor.mode(), &Mode::Block);
+    assert!(inhibitor.what().contains(&InhibitType::Sleep));
+    assert_eq!(inhibitor.process_id(), 4242);
+
+    assert_eq!(
+        manager.inhibitor_user_name(inhibitor).unwrap().as_deref(),
+        Some("user")
+    );
+    // root holds inhibitors without being a logind user
+    assert_eq!(
+        manager
+            .inhibitor_user_name(&inhibitors[1])
+            .unwrap()
+            .as_deref(),
+        Some("root")
+    );
+    assert_eq!(super::inhibitor::passwd_name(u32::MAX - 1).unwrap(), None);
+
+    let proc_root = std::env::temp_dir().join(format!("logind-zbus-proc-{}", std::process::id()));
+    std::fs::create_dir_all(proc_root.join("4242")).unwrap();
+    std::fs::write(
+        proc_root.join("4242/cmdline"),
+        b"mpv\0--no-video\0song.ogg\0",
+    )
+    .unwrap();
+    let cmdline = inhibitor.command_line_in(&proc_root);
+    let missing = inhibitors[1].command_line_in(&proc_root);
+    std::fs::remove_dir_all(&proc_root).unwrap();
+    assert_eq!(cmdline.unwrap(), ["mpv", "--no-video", "song.ogg"]);
+    assert_eq!(missing.unwrap_err().kind(), std::io::ErrorKind::NotFound);
+}
+
+#[test]
+fn power_diagnostics() {
+    let fake = future::block_on(testing::desktop());
+    let connection = fake.blocking_connection();
+    let manager = ManagerProxyBlocking::builder(&connection)
+        .cache_properties(CacheProperties::No)
+        .build()
+        .unwrap();
+    let diagnostics = PowerDiagnosticsBlocking::new(&manager);
+
+    let report = diagnostics.explain(PowerAction::Suspend).unwrap();
+    assert!(report.is_allowed(), "{report}");
+    assert_eq!(report.to_string(), "suspend is possible\n");
+
+    future::block_on(async {
+        fake.add_inhibitor(testing::FakeInhibitor {
+            what: InhibitType::Sleep | InhibitType::Idle,
+            who: "player".to_string(),
+            why: "Playing a video".to_string(),
+            mode: Mode::Block,
+            uid: 1000,
+            pid: 4242,
+        })
+        .await
+        .unwrap();
+        fake.update_manager(|m| m.can_suspend = IsSupported::Challenge)
+            .await
+            .unwrap();
+        fake.prepare_for_sleep(true).await.unwrap();
+    });
+    let report = diagnostics.explain(PowerAction::Suspend).unwrap();
+    assert!(!report.is_allowed());
+    assert!(!report.needs_authentication());
+    assert_eq!(report.blockers[0].process_id(), 4242);
+    assert!(report.in_progress);
+    assert_eq!(
+        report.to_string(),
+        "suspend is not possible right now\n\
+         - authentication is required\n\
+         - blocked by 'player' (uid 1000, pid 4242): Playing a video\n\
+         - the system is already preparing for sleep\n"
+    );
+
+    // Sleep inhibitors don't hold off shutdown
+    let report = diagnostics.explain(PowerAction::Reboot).unwrap();
+    assert!(report.is_allowed(), "{report}");
+
+    assert_eq!(
+        PowerAction::from_str("suspend-then-hibernate").unwrap(),
+        PowerAction::SuspendThenHibernate
+    );
+    let err = PowerAction::from_str("nap").unwrap_err();
+    assert_eq!(err.to_string(), "'nap' is not a valid PowerAction");
+}
+
+#[test]
+fn power_capabilities() {
+    let fake = future::block_on(testing::desktop());
+    future::block_on(fake.update_manager(|m| {
+        m.can_hibernate = IsSupported::Challenge;
+        m.missing = vec!["CanSuspendThenHibernate", "CanRebootToBootLoaderEntry"];
+    }))
+    .unwrap();
+    let connection = fake.blocking_connection();
+    let manager = ManagerProxyBlocking::new(&connection).unwrap();
+
+    let capabilities = manager.power_capabilities().unwrap();
+    assert_eq!(capabilities.suspend, Some(IsSupported::Yes));
+    assert_eq!(
+        capabilities.get(PowerAction::Hibernate),
+        Some(&IsSupported::Challenge)
+    );
+    assert_eq!(capabilities.suspend_then_hibernate, None);
+    assert_eq!(capabilities.reboot_to_boot_loader_entry, None);
+    assert_eq!(
+        capabilities.reboot_to_boot_loader_menu,
+        Some(IsSupported::NA)
+    );
+
+    let err = Error::from(manager.can_suspend_then_hibernate().unwrap_err());
+    assert!(matches!(err, Error::UnknownMethod(_)), "{err}");
+}
+
+#[test]
+fn power_actions() {
+    let fake = future::block_on(testing::desktop());
+    future::block_on(fake.update_manager(|m| m.can_hibernate = IsSupported::No)).unwrap();
+    let connection = fake.blocking_connection();
+    let manager = ManagerProxyBlocking::new(&connection).unwrap();
+
+    assert_eq!(manager.can(PowerAction::Suspend).unwrap(), IsSupported::Yes);
+    assert_eq!(
+        manager.can(PowerAction::Hibernate).unwrap(),
+        IsSupported::No
+    );
+    for action in [PowerAction::Suspend, PowerAction::Reboot] {
+        manager.perform(action, true).unwrap();
+    }
+    let calls = fake.calls();
+    assert_eq!(calls[0].member, "Suspend");
+    assert_eq!(calls[0].args[0], OwnedValue::from(true));
+    assert_eq!(calls[1].member, "Reboot");
+
+    for action in PowerAction::ALL {
+        assert_eq!(action.to_string().parse::<PowerAction>().unwrap(), action);
+    }
+    assert_eq!(PowerAction::signature(), "s");
+    let err = "sleep-forever".parse::<PowerAction>().unwrap_err();
+    assert_eq!(err.type_name(), "PowerAction");
+}
+
+#[test]
+fn power_flags() {
+    let fake = future::block_on(testing::desktop());
+    let connection = fake.blocking_connection();
+    let manager = ManagerProxyBlocking::new(&connection).unwrap();
+
+    manager
+        .reboot_with_flags(PowerFlags::SOFT_REBOOT | PowerFlags::ROOT_CHECK_INHIBITORS)
+        .unwrap();
+    manager
+        .perform_with_flags(PowerAction::Suspend, PowerFlags::SKIP_INHIBITORS)
+        .unwrap();
+    let calls = fake.calls();
+    assert_eq!(calls[0].member, "RebootWithFlags");
+    assert_eq!(calls[0].args[0], OwnedValue::from(0b101u64));
+    assert_eq!(calls[1].member, "SuspendWithFlags");
+    assert_eq!(calls[1].args[0], OwnedValue::from(1u64 << 4));
+
+    let err = manager
+        .power_off_with_flags(PowerFlags::REBOOT_VIA_KEXEC)
+        .unwrap_err();
+    assert_eq!(
+        Error::from(err).dbus_name(),
+        Some("org.freedesktop.DBus.Error.InvalidArgs")
+    );
+
+    future::block_on(fake.update_manager(|m| m.missing = vec!["HaltWithFlags"])).unwrap();
+    let err = Error::from(manager.halt_with_flags(PowerFlags::empty()).unwrap_err());
+    assert!(matches!(err, Error::UnknownMethod(_)), "{err}");
+}
+
+#[test]
+fn sleep() {
+    let fake = future::block_on(testing::desktop());
+    let connection = fake.blocking_connection();
+    let manager = ManagerProxyBlocking::new(&connection).unwrap();
+
+    let operation = manager.sleep_operation().unwrap();
+    assert_eq!(
+        operation.modes(),
+        [
+            SleepMode::SuspendThenHibernate,
+            SleepMode::Suspend,
+            SleepMode::Hibernate
+        ]
+    );
+    assert_eq!(manager.can(PowerAction::Sleep).unwrap(), IsSupported::Yes);
+
+    manager.sleep(PowerFlags::SKIP_INHIBITORS).unwrap();
+    manager.perform(PowerAction::Sleep, true).unwrap();
+    manager.perform(PowerAction::Sleep, false).unwrap();
+    let calls = fake.calls();
+    assert_eq!(calls.len(), 3);
+    assert!(calls.iter().all(|call| call.member == "Sleep"));
+    assert_eq!(calls[0].args[0], OwnedValue::from(1u64 << 4));
+    assert_eq!(calls[1].args[0], OwnedValue::from(0u64));
+
+    future::block_on(fake.update_manager(|m| m.missing = vec!["CanSleep", "Sleep"])).unwrap();
+    let capabilities = manager.power_capabilities().unwrap();
+    assert_eq!(capabilities.get(PowerAction::Sleep), None);
+    let err = Error::from(manager.perform(PowerAction::Sleep, false).unwrap_err());
+    assert!(matches!(err, Error::UnknownMethod(_)), "{err}");
+}
+
+#[test]
+fn sessions_extended() {
+    let fake = future::block_on(testing::desktop());
+    future::block_on(fake.add_session(FakeSession {
+        class: SessionClass::Background,
+        idle_hint: true,
+        idle_since_hint_monotonic: 5_000_000,
+        leader: 4321,
+        ..FakeSession::new("c1", 1000)
+    }))
+    .unwrap();
+    let connection = fake.blocking_connection();
+    let manager = ManagerProxyBlocking::new(&connection).unwrap();
+
+    let mut sessions = manager.list_sessions_ex().unwrap();
+    sessions.sort_by(|a, b| a.sid().cmp(b.sid()));
+    assert_eq!(sessions.len(), 2);
+    let desktop = &sessions[0];
+    assert_eq!(desktop.sid(), "2");
+    assert_eq!(desktop.user(), "user");
+    assert_eq!(desktop.seat(), "seat0");
+    assert_eq!(desktop.leader(), 1234);
+    assert_eq!(desktop.class(), &SessionClass::User);
+    assert_eq!(desktop.tty(), "tty2");
+    assert!(!desktop.is_idle());
+    assert_eq!(desktop.session_type(), None);
+    let background = &sessions[1];
+    assert_eq!(background.seat(), "");
+    assert_eq!(background.class(), &SessionClass::Background);
+    assert!(background.is_idle());
+    assert_eq!(background.idle_since().as_micros(), 5_000_000);
+
+    // The type is read on top of `ListSessionsEx`
+    let mut extended = manager.list_sessions_extended().unwrap();
+    extended.sort_by(|a, b| a.sid().cmp(b.sid()));
+    assert_eq!(extended[0].session_type(), Some(&SessionType::Wayland));
+    assert_eq!(extended[1].session_type(), Some(&SessionType::TTY));
+    assert_eq!(extended[1].leader(), 4321);
+
+    future::block_on(fake.update_manager(|m| m.missing = vec!["ListSessionsEx"])).unwrap();
+    let err = Error::from(manager.list_sessions_ex().unwrap_err());
+    assert!(matches!(err, Error::UnknownMethod(_)), "{err}");
+    let mut fallback = manager.list_sessions_extended().unwrap();
+    fallback.sort_by(|a, b| a.sid().cmp(b.sid()));
+    assert_eq!(fallback, extended);
+}
+
+#[test]
+fn sessions_extended_vanished() {
+    let fake = future::block_on(testing::desktop());
+    future::block_on(fake.add_session(FakeSession::new("c1", 1000))).unwrap();
+    let connection = fake.connection().clone();
+    let manager = future::block_on(ManagerProxy::new(&connection)).unwrap();
+
+    let mut sessions = future::block_on(manager.list_sessions()).unwrap();
+    sessions.sort_by(|a, b| a.sid().cmp(b.sid()));
+    let gone = sessions.pop().unwrap();
+    assert_eq!(gone.sid(), "c1");
+    let mut sessions_ex = future::block_on(manager.list_sessions_ex()).unwrap();
+    sessions_ex.sort_by(|a, b| a.sid().cmp(b.sid()));
+    let gone_ex = sessions_ex.pop().unwrap();
+    future::block_on(fake.remove_session("c1")).unwrap();
+    assert_eq!(
+        future::block_on(manager.session_details(gone)).unwrap(),
+        None
+    );
+    assert_eq!(future::block_on(manager.with_type(gone_ex)).unwrap(), None);
+
+    future::block_on(fake.update_manager(|m| m.missing = vec!["ListSessionsEx"])).unwrap();
+    let listed = future::block_on(manager.list_sessions_extended()).unwrap();
+    assert_eq!(listed.len(), 1);
+    assert_eq!(listed[0].sid(), "2");
+}
+
+#[test]
+fn newer_properties() {
+    let fake = future::block_on(testing::desktop());
+    future::block_on(fake.update_manager(|m| {
+        m.designated_maintenance_time = "Sun 03:00".to_string();
+        m.stop_idle_session_usec = 3_600_000_000;
+    }))
+    .unwrap();
+    let connection = fake.blocking_connection();
+    let manager = ManagerProxyBlocking::new(&connection).unwrap();
+
+    assert_eq!(
+        manager.handle_reboot_key_long_press().unwrap(),
+        HandleAction::PowerOff
+    );
+    assert_eq!(
+        manager.handle_secure_attention_key().unwrap(),
+        HandleAction::SecureAttentionKey
+    );
+    assert_eq!(
+        manager.stop_idle_session_delay().unwrap(),
+        Some(Duration::from_secs(3600))
+    );
+    let maintenance = manager.designated_maintenance_time().unwrap();
+    assert_eq!(maintenance.calendar_event(), Some("Sun 03:00"));
+
+    // As on systemd 249, which has none of these
+    future::block_on(fake.update_manager(|m| {
+        m.designated_maintenance_time.clear();
+        m.missing = vec!["HandleRebootKey", "StopIdleSessionUSec"];
+    }))
+    .unwrap();
+    let manager = ManagerProxyBlocking::builder(&connection)
+        .cache_properties(CacheProperties::No)
+        .build()
+        .unwrap();
+    assert_eq!(
+        manager
+            .designated_maintenance_time()
+            .unwrap()
+            .calendar_event(),
+        None
+    );
+    let err = Error::from(manager.handle_reboot_key().unwrap_err());
+    assert!(matches!(err, Error::UnknownProperty(_)), "{err}");
+    let err = Error::from(manager.stop_idle_session_USec().unwrap_err());
+    assert!(matches!(err, Error::UnknownProperty(_)), "{err}");
+    assert_eq!(
+        err.dbus_name(),
+        Some("org.freedesktop.DBus.Error.UnknownProperty")
+    );
+    assert!(err.is_unsupported());
+}
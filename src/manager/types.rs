@@ -1,9 +1,19 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::fmt;
+use std::io;
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign};
+use std::os::unix::io::{AsFd, AsRawFd, BorrowedFd, IntoRawFd, OwnedFd, RawFd};
 use std::str::FromStr;
-use zbus::fdo;
-use zbus::zvariant::{OwnedObjectPath, OwnedValue, Structure, Type};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use zbus::zvariant::{self, OwnedObjectPath, OwnedValue, Signature, Structure, Type};
 
-use crate::{enum_impl_serde_str, enum_impl_str_conv, IntoPath};
+use crate::{
+    dup_fd, enum_impl_serde_str, enum_impl_str_conv, impl_try_from_owned_as_str,
+    session::{SessionClass, SessionType},
+    IntoPath, MonotonicStamp, ParseError,
+};
 
 /// Basic user information
 #[derive(Debug, PartialEq, Eq, Clone, Type, Serialize, Deserialize)]
@@ -40,21 +50,350 @@ impl IntoPath for UserInfo {
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Clone, Type, Serialize, Deserialize)]
-pub struct ScheduledShutdown {
-    /// Name of the shutdown
-    id: String,
-    /// Time in micros
-    time: u64,
+/// The kind of shutdown given to `ScheduleShutdown`
+#[derive(Debug, PartialEq, Eq, Clone, Type)]
+#[zvariant(signature = "s")]
+pub enum ShutdownKind {
+    PowerOff,
+    Reboot,
+    Halt,
+    /// Reboot into the kernel loaded with `kexec`
+    Kexec,
+    /// Restart userspace only, keeping the running kernel
+    SoftReboot,
+    /// Like `PowerOff`, but only the wall messages are sent and nothing is shut down
+    DryPowerOff,
+    DryReboot,
+    DryHalt,
+    DryKexec,
+    DrySoftReboot,
+    /// A value not known to this crate, as sent by logind
+    Other(String),
+}
+enum_impl_serde_str!(ShutdownKind);
+impl_try_from_owned_as_str!(ShutdownKind);
+enum_impl_str_conv!(ShutdownKind, {
+    "poweroff": PowerOff,
+    "reboot": Reboot,
+    "halt": Halt,
+    "kexec": Kexec,
+    "soft-reboot": SoftReboot,
+    "dry-poweroff": DryPowerOff,
+    "dry-reboot": DryReboot,
+    "dry-halt": DryHalt,
+    "dry-kexec": DryKexec,
+    "dry-soft-reboot": DrySoftReboot,
+});
+
+/// The metadata sent with `PrepareForShutdownWithMetadata`
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ShutdownMetadata {
+    kind: Option<ShutdownKind>,
+    /// Entries not parsed into a field
+    other: HashMap<String, OwnedValue>,
+}
+
+impl ShutdownMetadata {
+    /// What the system is about to do, from the `type` entry
+    pub fn kind(&self) -> Option<&ShutdownKind> {
+        self.kind.as_ref()
+    }
+
+    /// Whether the system comes back up afterwards: a reboot, kexec or soft-reboot
+    pub fn is_reboot(&self) -> bool {
+        matches!(
+            self.kind,
+            Some(ShutdownKind::Reboot | ShutdownKind::Kexec | ShutdownKind::SoftReboot)
+        )
+    }
+
+    /// An entry this crate does not parse, as logind sent it
+    pub fn get(&self, key: &str) -> Option<&OwnedValue> {
+        self.other.get(key)
+    }
+}
+
+impl From<HashMap<String, OwnedValue>> for ShutdownMetadata {
+    fn from(mut metadata: HashMap<String, OwnedValue>) -> Self {
+        let kind = metadata
+            .remove("type")
+            .and_then(|kind| ShutdownKind::try_from(kind).ok());
+        Self {
+            kind,
+            other: metadata,
+        }
+    }
+}
+
+/// What logind does on a key press, lid switch or idle timeout, as set by the `Handle*` and
+/// `IdleAction` options of `logind.conf`
+#[derive(Debug, PartialEq, Eq, Clone, Type)]
+#[zvariant(signature = "s")]
+pub enum HandleAction {
+    Ignore,
+    PowerOff,
+    Reboot,
+    Halt,
+    Kexec,
+    Suspend,
+    Hibernate,
+    HybridSleep,
+    SuspendThenHibernate,
+    /// Suspend, hibernate or both, as configured in `sleep.conf`
+    Sleep,
+    /// Lock all sessions
+    Lock,
+    FactoryReset,
+    /// Tell the active session's compositor the secure attention key was pressed
+    SecureAttentionKey,
+    /// A value not known to this crate, as sent by logind
+    Other(String),
+}
+enum_impl_serde_str!(HandleAction);
+impl_try_from_owned_as_str!(HandleAction);
+enum_impl_str_conv!(HandleAction, {
+    "ignore": Ignore,
+    "poweroff": PowerOff,
+    "reboot": Reboot,
+    "halt": Halt,
+    "kexec": Kexec,
+    "suspend": Suspend,
+    "hibernate": Hibernate,
+    "hybrid-sleep": HybridSleep,
+    "suspend-then-hibernate": SuspendThenHibernate,
+    "sleep": Sleep,
+    "lock": Lock,
+    "factory-reset": FactoryReset,
+    "secure-attention-key": SecureAttentionKey,
+});
+
+/// A power action, named as in `systemctl` and logind's own configuration
+///
+/// Unlike the enums for values logind sends, parsing an unknown name fails with a
+/// [`ParseError`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, Type)]
+#[zvariant(signature = "s")]
+pub enum PowerAction {
+    PowerOff,
+    Reboot,
+    Halt,
+    Suspend,
+    Hibernate,
+    HybridSleep,
+    SuspendThenHibernate,
+    /// Whichever of the others the `SleepOperation` property picks
+    Sleep,
+}
+
+impl PowerAction {
+    pub const ALL: [PowerAction; 8] = [
+        Self::PowerOff,
+        Self::Reboot,
+        Self::Halt,
+        Self::Suspend,
+        Self::Hibernate,
+        Self::HybridSleep,
+        Self::SuspendThenHibernate,
+        Self::Sleep,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::PowerOff => "poweroff",
+            Self::Reboot => "reboot",
+            Self::Halt => "halt",
+            Self::Suspend => "suspend",
+            Self::Hibernate => "hibernate",
+            Self::HybridSleep => "hybrid-sleep",
+            Self::SuspendThenHibernate => "suspend-then-hibernate",
+            Self::Sleep => "sleep",
+        }
+    }
+
+    /// Whether this puts the system to sleep rather than shutting it down
+    pub fn is_sleep(&self) -> bool {
+        matches!(
+            self,
+            Self::Suspend
+                | Self::Hibernate
+                | Self::HybridSleep
+                | Self::SuspendThenHibernate
+                | Self::Sleep
+        )
+    }
+
+    /// The inhibitor type that holds this action off
+    pub fn inhibit_type(&self) -> InhibitType {
+        if self.is_sleep() {
+            InhibitType::Sleep
+        } else {
+            InhibitType::Shutdown
+        }
+    }
+}
+
+impl FromStr for PowerAction {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|action| action.as_str() == s)
+            .ok_or_else(|| ParseError::new("PowerAction", s))
+    }
+}
+
+impl<'a> From<&'a PowerAction> for &'a str {
+    fn from(action: &'a PowerAction) -> Self {
+        action.as_str()
+    }
+}
+
+impl fmt::Display for PowerAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+enum_impl_serde_str!(PowerAction);
+
+bitflags::bitflags! {
+    /// Flags for the `*WithFlags` power methods, the `SD_LOGIND_*` constants of systemd
+    #[derive(Default)]
+    pub struct PowerFlags: u64 {
+        /// Check inhibitors even when called by root, who otherwise ignores them
+        const ROOT_CHECK_INHIBITORS = 1 << 0;
+        /// Reboot into the kernel loaded with `kexec`, if one is. Reboot only.
+        const REBOOT_VIA_KEXEC = 1 << 1;
+        /// Restart userspace only, keeping the running kernel. Reboot only.
+        const SOFT_REBOOT = 1 << 2;
+        /// Soft reboot only if a new root is set up in `/run/nextroot/`. Reboot only.
+        const SOFT_REBOOT_IF_NEXTROOT_SET_UP = 1 << 3;
+        /// Go ahead in spite of block inhibitors, when the caller is allowed to
+        const SKIP_INHIBITORS = 1 << 4;
+    }
+}
+
+impl Type for PowerFlags {
+    fn signature() -> Signature<'static> {
+        u64::signature()
+    }
+}
+
+impl Serialize for PowerFlags {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_u64(self.bits())
+    }
+}
+
+/// Flags unknown to this crate are dropped
+impl<'de> Deserialize<'de> for PowerFlags {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        u64::deserialize(deserializer).map(Self::from_bits_truncate)
+    }
+}
+
+/// A way of sleeping that `Sleep` may pick
+#[derive(Debug, PartialEq, Eq, Clone, Type)]
+#[zvariant(signature = "s")]
+pub enum SleepMode {
+    Suspend,
+    Hibernate,
+    HybridSleep,
+    SuspendThenHibernate,
+    /// A value not known to this crate, as sent by logind
+    Other(String),
+}
+enum_impl_serde_str!(SleepMode);
+impl_try_from_owned_as_str!(SleepMode);
+enum_impl_str_conv!(SleepMode, {
+    "suspend": Suspend,
+    "hibernate": Hibernate,
+    "hybrid-sleep": HybridSleep,
+    "suspend-then-hibernate": SuspendThenHibernate,
+});
+
+/// The `DesignatedMaintenanceTime` property: when automatic updates and similar
+/// maintenance may reboot the system, as a systemd calendar event such as `"Sun 03:00"`
+#[derive(Debug, Default, PartialEq, Eq, Clone, Type, Serialize, Deserialize)]
+pub struct MaintenanceTime(String);
+
+impl MaintenanceTime {
+    /// The calendar event, or `None` when no maintenance time is set
+    pub fn calendar_event(&self) -> Option<&str> {
+        Some(self.0.as_str()).filter(|event| !event.is_empty())
+    }
+}
+
+impl TryFrom<OwnedValue> for MaintenanceTime {
+    type Error = zbus::Error;
+
+    fn try_from(value: OwnedValue) -> Result<Self, Self::Error> {
+        Ok(Self(<String>::try_from(value)?))
+    }
+}
+
+/// The `SleepOperation` property: the sleep modes `Sleep` tries, in order, as set in
+/// `sleep.conf`. It uses the first the system supports.
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct SleepOperation(Vec<SleepMode>);
+
+impl SleepOperation {
+    pub fn modes(&self) -> &[SleepMode] {
+        &self.0
+    }
+
+    pub fn into_inner(self) -> Vec<SleepMode> {
+        self.0
+    }
+}
+
+impl From<SleepOperation> for Vec<SleepMode> {
+    fn from(operation: SleepOperation) -> Self {
+        operation.0
+    }
+}
+
+impl TryFrom<OwnedValue> for SleepOperation {
+    type Error = zbus::Error;
+
+    fn try_from(value: OwnedValue) -> Result<Self, Self::Error> {
+        let modes = <Vec<String>>::try_from(value)?;
+        Ok(Self(
+            modes
+                .iter()
+                .map(|mode| SleepMode::from(mode.as_str()))
+                .collect(),
+        ))
+    }
 }
 
+/// The `ScheduledShutdown` property: which shutdown is scheduled and when, if any
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ScheduledShutdown(Option<(ShutdownKind, SystemTime)>);
+
 impl ScheduledShutdown {
-    pub fn id(&self) -> &str {
-        &self.id
+    pub fn kind(&self) -> Option<&ShutdownKind> {
+        self.0.as_ref().map(|(kind, _)| kind)
+    }
+
+    pub fn time(&self) -> Option<SystemTime> {
+        self.0.as_ref().map(|(_, time)| *time)
     }
 
-    pub fn time(&self) -> u64 {
-        self.time
+    pub fn into_inner(self) -> Option<(ShutdownKind, SystemTime)> {
+        self.0
+    }
+}
+
+impl From<ScheduledShutdown> for Option<(ShutdownKind, SystemTime)> {
+    fn from(scheduled: ScheduledShutdown) -> Self {
+        scheduled.0
     }
 }
 
@@ -63,20 +402,151 @@ impl TryFrom<OwnedValue> for ScheduledShutdown {
 
     fn try_from(value: OwnedValue) -> Result<Self, Self::Error> {
         let value = <Structure>::try_from(value)?;
-        Ok(Self {
-            id: <String>::try_from(value.fields()[0].clone())?,
-            time: <u64>::try_from(value.fields()[1].clone())?,
-        })
+        let kind = <String>::try_from(value.fields()[0].clone())?;
+        let usec = <u64>::try_from(value.fields()[1].clone())?;
+        // logind reports `("", 0)` when nothing is scheduled
+        if kind.is_empty() || usec == 0 {
+            return Ok(Self(None));
+        }
+        let time = UNIX_EPOCH + Duration::from_micros(usec);
+        Ok(Self(Some((ShutdownKind::from(kind.as_str()), time))))
+    }
+}
+
+/// A span of time in microseconds, as the `*USec` properties hold them, where `u64::MAX`
+/// stands for infinity
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Type, Serialize, Deserialize,
+)]
+pub struct TimeSpan(u64);
+
+impl TimeSpan {
+    pub const INFINITY: Self = Self(u64::MAX);
+
+    pub fn from_micros(usec: u64) -> Self {
+        Self(usec)
+    }
+
+    pub fn as_micros(&self) -> u64 {
+        self.0
+    }
+
+    pub fn is_infinite(&self) -> bool {
+        *self == Self::INFINITY
+    }
+
+    /// The span as a `Duration`, `None` if it is infinite
+    pub fn to_duration(&self) -> Option<Duration> {
+        (!self.is_infinite()).then(|| Duration::from_micros(self.0))
+    }
+}
+
+/// Spans too long for `u64` microseconds become infinite
+impl From<Duration> for TimeSpan {
+    fn from(duration: Duration) -> Self {
+        Self(duration.as_micros().try_into().unwrap_or(u64::MAX))
+    }
+}
+
+/// `None` is infinite
+impl From<Option<Duration>> for TimeSpan {
+    fn from(duration: Option<Duration>) -> Self {
+        duration.map_or(Self::INFINITY, Self::from)
+    }
+}
+
+impl From<TimeSpan> for Option<Duration> {
+    fn from(span: TimeSpan) -> Self {
+        span.to_duration()
+    }
+}
+
+impl TryFrom<OwnedValue> for TimeSpan {
+    type Error = zbus::Error;
+
+    fn try_from(value: OwnedValue) -> Result<Self, Self::Error> {
+        Ok(Self(<u64>::try_from(value)?))
+    }
+}
+
+/// The `RebootToBootLoaderMenu` property, and what `SetRebootToBootLoaderMenu` takes
+///
+/// On the wire it is the menu timeout in microseconds, with `u64::MAX` for no menu and zero
+/// for a menu that waits for the user.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BootLoaderMenu {
+    /// Boot as usual, without stopping in the menu
+    Skip,
+    /// Show the menu until an entry is picked
+    NoTimeout,
+    /// Show the menu, booting the default entry once the timeout passes
+    Timeout(Duration),
+}
+
+impl BootLoaderMenu {
+    pub fn from_micros(usec: u64) -> Self {
+        match usec {
+            u64::MAX => Self::Skip,
+            0 => Self::NoTimeout,
+            usec => Self::Timeout(Duration::from_micros(usec)),
+        }
+    }
+
+    /// Timeouts too long for `u64` microseconds are clamped just short of [`Self::Skip`]
+    pub fn as_micros(&self) -> u64 {
+        match self {
+            Self::Skip => u64::MAX,
+            Self::NoTimeout => 0,
+            Self::Timeout(timeout) => timeout
+                .as_micros()
+                .try_into()
+                .unwrap_or(u64::MAX - 1)
+                .clamp(1, u64::MAX - 1),
+        }
+    }
+}
+
+impl Type for BootLoaderMenu {
+    fn signature() -> Signature<'static> {
+        u64::signature()
+    }
+}
+
+impl Serialize for BootLoaderMenu {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_u64(self.as_micros())
+    }
+}
+
+impl<'de> Deserialize<'de> for BootLoaderMenu {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        u64::deserialize(deserializer).map(Self::from_micros)
+    }
+}
+
+impl TryFrom<OwnedValue> for BootLoaderMenu {
+    type Error = zbus::Error;
+
+    fn try_from(value: OwnedValue) -> Result<Self, Self::Error> {
+        Ok(Self::from_micros(<u64>::try_from(value)?))
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Clone, Copy, Type)]
+#[derive(Debug, PartialEq, Eq, Clone, Type)]
 #[zvariant(signature = "s")]
 pub enum IsSupported {
     NA,
     Yes,
     No,
     Challenge,
+    /// A value not known to this crate, as sent by logind
+    Other(String),
 }
 enum_impl_serde_str!(IsSupported);
 enum_impl_str_conv!(IsSupported, {
@@ -86,45 +556,191 @@ enum_impl_str_conv!(IsSupported, {
     "challenge": Challenge,
 });
 
-#[derive(Debug, PartialEq, Eq, Clone, Type)]
+/// A set of [`InhibitType`]s, sent to and from logind as a colon separated list such as
+/// `"sleep:shutdown"`
+///
+/// Types keep the order they were added in, and each is held at most once.
+#[derive(Debug, Default, Clone, Type)]
 #[zvariant(signature = "s")]
 pub struct InhibitTypes(Vec<InhibitType>);
 
+impl InhibitTypes {
+    pub fn new(types: &[InhibitType]) -> Self {
+        types.iter().cloned().collect()
+    }
+
+    pub fn empty() -> Self {
+        Self(Vec::new())
+    }
+
+    pub fn contains(&self, t: &InhibitType) -> bool {
+        self.0.contains(t)
+    }
+
+    /// Add `t`, returning false if it was already in the set
+    pub fn insert(&mut self, t: InhibitType) -> bool {
+        if self.contains(&t) {
+            return false;
+        }
+        self.0.push(t);
+        true
+    }
+
+    /// Remove `t`, returning false if it was not in the set
+    pub fn remove(&mut self, t: &InhibitType) -> bool {
+        let len = self.0.len();
+        self.0.retain(|held| held != t);
+        self.0.len() != len
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, InhibitType> {
+        self.0.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// Sets are equal when they hold the same types, in any order
+impl PartialEq for InhibitTypes {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().all(|t| other.contains(t))
+    }
+}
+
+impl Eq for InhibitTypes {}
+
+impl From<InhibitType> for InhibitTypes {
+    fn from(t: InhibitType) -> Self {
+        Self(vec![t])
+    }
+}
+
+impl FromIterator<InhibitType> for InhibitTypes {
+    fn from_iter<I: IntoIterator<Item = InhibitType>>(iter: I) -> Self {
+        let mut types = Self::empty();
+        types.extend(iter);
+        types
+    }
+}
+
+impl Extend<InhibitType> for InhibitTypes {
+    fn extend<I: IntoIterator<Item = InhibitType>>(&mut self, iter: I) {
+        for t in iter {
+            self.insert(t);
+        }
+    }
+}
+
+impl IntoIterator for InhibitTypes {
+    type Item = InhibitType;
+    type IntoIter = std::vec::IntoIter<InhibitType>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a InhibitTypes {
+    type Item = &'a InhibitType;
+    type IntoIter = std::slice::Iter<'a, InhibitType>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+/// The union of both sets
+impl BitOr for InhibitTypes {
+    type Output = Self;
+
+    fn bitor(mut self, rhs: Self) -> Self {
+        self.extend(rhs);
+        self
+    }
+}
+
+impl BitOr<InhibitType> for InhibitTypes {
+    type Output = Self;
+
+    fn bitor(mut self, rhs: InhibitType) -> Self {
+        self.insert(rhs);
+        self
+    }
+}
+
+impl BitOr for InhibitType {
+    type Output = InhibitTypes;
+
+    fn bitor(self, rhs: Self) -> InhibitTypes {
+        InhibitTypes::from(self) | rhs
+    }
+}
+
+impl BitOrAssign for InhibitTypes {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.extend(rhs);
+    }
+}
+
+/// The types in both sets
+impl BitAnd for InhibitTypes {
+    type Output = Self;
+
+    fn bitand(mut self, rhs: Self) -> Self {
+        self.0.retain(|t| rhs.contains(t));
+        self
+    }
+}
+
+impl BitAndAssign for InhibitTypes {
+    fn bitand_assign(&mut self, rhs: Self) {
+        self.0.retain(|t| rhs.contains(t));
+    }
+}
+
+/// An empty string is the empty set
 impl FromStr for InhibitTypes {
-    type Err = fdo::Error;
+    type Err = Infallible;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut buf = Vec::new();
-        for chunk in s.split(':') {
-            buf.push(InhibitType::from_str(chunk)?);
-        }
-        Ok(Self(buf))
+        Ok(s.split(':')
+            .filter(|t| !t.is_empty())
+            .map(InhibitType::from)
+            .collect())
     }
 }
 
 impl From<InhibitTypes> for String {
     fn from(s: InhibitTypes) -> Self {
-        let mut string = String::new();
-        for (i, inhibit) in s.0.iter().enumerate() {
-            if i > 0 && i < s.0.len() {
-                string.push(':');
-            }
-            string.push_str((*inhibit).into());
-        }
-        string
+        String::from(&s)
     }
 }
 
 impl From<&InhibitTypes> for String {
     fn from(s: &InhibitTypes) -> Self {
-        let mut string = String::new();
-        for (i, inhibit) in s.0.iter().enumerate() {
-            if i > 0 && i < s.0.len() {
-                string.push(':');
-            }
-            string.push_str((*inhibit).into());
-        }
-        string
+        let types: Vec<&str> = s.iter().map(InhibitType::as_str).collect();
+        types.join(":")
+    }
+}
+
+impl fmt::Display for InhibitTypes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&String::from(self))
+    }
+}
+
+impl TryFrom<OwnedValue> for InhibitTypes {
+    type Error = zbus::Error;
+
+    fn try_from(value: OwnedValue) -> Result<Self, Self::Error> {
+        let value = <String>::try_from(value)?;
+        InhibitTypes::from_str(&value).map_err(|never| match never {})
     }
 }
 
@@ -147,7 +763,7 @@ impl<'de> Deserialize<'de> for InhibitTypes {
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Copy, Clone, Type)]
+#[derive(Debug, PartialEq, Eq, Clone, Type)]
 #[zvariant(signature = "s")]
 pub enum InhibitType {
     Shutdown,
@@ -157,6 +773,9 @@ pub enum InhibitType {
     HandleSuspendKey,
     HandleHibernateKey,
     HandleLidSwitch,
+    HandleRebootKey,
+    /// A value not known to this crate, as sent by logind
+    Other(String),
 }
 enum_impl_serde_str!(InhibitType);
 enum_impl_str_conv!(InhibitType, {
@@ -167,6 +786,7 @@ enum_impl_str_conv!(InhibitType, {
     "handle-suspend-key": HandleSuspendKey,
     "handle-hibernate-key": HandleHibernateKey,
     "handle-lid-switch": HandleLidSwitch,
+    "handle-reboot-key": HandleRebootKey,
 });
 
 #[derive(Debug, PartialEq, Eq, Clone, Type, Serialize, Deserialize)]
@@ -183,19 +803,121 @@ pub struct Inhibitor {
     process_id: u32,
 }
 
+impl Inhibitor {
+    pub fn what(&self) -> &InhibitTypes {
+        &self.what
+    }
+
+    pub fn who(&self) -> &str {
+        &self.who
+    }
+
+    pub fn why(&self) -> &str {
+        &self.why
+    }
+
+    pub fn mode(&self) -> &Mode {
+        &self.mode
+    }
+
+    /// The user that took the lock
+    pub fn user_id(&self) -> u32 {
+        self.user_id
+    }
+
+    /// The process that took the lock
+    pub fn process_id(&self) -> u32 {
+        self.process_id
+    }
+}
+
+/// An inhibitor lock as returned by [`ManagerProxy::inhibit`](super::ManagerProxy::inhibit)
+///
+/// logind holds the lock for as long as the file descriptor (or any duplicate of it) stays
+/// open, so the lock is released when this is dropped.
+#[derive(Debug)]
+pub struct InhibitorLock(OwnedFd);
+
+impl InhibitorLock {
+    /// Release the lock now. This is the same as dropping it.
+    pub fn release(self) {
+        drop(self)
+    }
+
+    /// Turn the lock into a file descriptor that is inherited across `exec`, for handing the
+    /// lock to a child process. The lock is held until every copy of the fd is closed.
+    pub fn into_inheritable_fd(self) -> io::Result<OwnedFd> {
+        let fd = self.0;
+        // SAFETY: `fd` is a valid open file descriptor owned by us
+        let flags = unsafe { libc::fcntl(fd.as_raw_fd(), libc::F_GETFD) };
+        if flags < 0
+            || unsafe { libc::fcntl(fd.as_raw_fd(), libc::F_SETFD, flags & !libc::FD_CLOEXEC) } < 0
+        {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(fd)
+    }
+}
+
+impl AsFd for InhibitorLock {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.0.as_fd()
+    }
+}
+
+impl AsRawFd for InhibitorLock {
+    fn as_raw_fd(&self) -> RawFd {
+        self.0.as_raw_fd()
+    }
+}
+
+impl IntoRawFd for InhibitorLock {
+    fn into_raw_fd(self) -> RawFd {
+        self.0.into_raw_fd()
+    }
+}
+
+impl From<InhibitorLock> for OwnedFd {
+    fn from(lock: InhibitorLock) -> Self {
+        lock.0
+    }
+}
+
+impl Type for InhibitorLock {
+    fn signature() -> Signature<'static> {
+        zvariant::Fd::signature()
+    }
+}
+
+impl<'de> Deserialize<'de> for InhibitorLock {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let fd = zvariant::Fd::deserialize(deserializer)?;
+        dup_fd(&fd).map(Self).map_err(serde::de::Error::custom)
+    }
+}
+
 /// Used to determine behaviour of inhibitors
-#[derive(Debug, PartialEq, Eq, Copy, Clone, Type)]
+#[derive(Debug, PartialEq, Eq, Clone, Type)]
 #[zvariant(signature = "s")]
 pub enum Mode {
     /// Inhibitor is mandatory
     Block,
     /// Inhibitor delays to a certain time
     Delay,
+    /// Inhibitor is mandatory, but may be overridden by privileged callers without
+    /// requesting it explicitly
+    BlockWeak,
+    /// A value not known to this crate, as sent by logind
+    Other(String),
 }
 enum_impl_serde_str!(Mode);
 enum_impl_str_conv!(Mode, {
     "block": Block,
     "delay": Delay,
+    "block-weak": BlockWeak,
 });
 
 #[derive(Debug, PartialEq, Eq, Type, Serialize, Deserialize)]
@@ -243,3 +965,187 @@ impl IntoPath for SessionInfo {
         &self.path
     }
 }
+
+/// A session as listed by `ListSessionsEx`, with the details a session table needs
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SessionInfoEx {
+    /// Session ID
+    sid: String,
+    /// User ID
+    uid: u32,
+    /// Name of session user
+    user: String,
+    /// The session seat label, empty for a session without a seat
+    seat: String,
+    /// PID of the session leader
+    leader: u32,
+    class: SessionClass,
+    /// The TTY the session runs on, if any
+    tty: String,
+    idle: bool,
+    /// When the session went idle
+    idle_since: MonotonicStamp,
+    /// DBUS path for this session
+    path: OwnedObjectPath,
+    /// Not part of the `ListSessionsEx` reply, and read separately
+    session_type: Option<SessionType>,
+}
+
+/// `SessionInfoEx` as `ListSessionsEx` sends it, without the session type
+type SessionInfoExWire = (
+    String,
+    u32,
+    String,
+    String,
+    u32,
+    SessionClass,
+    String,
+    bool,
+    MonotonicStamp,
+    OwnedObjectPath,
+);
+
+impl Type for SessionInfoEx {
+    fn signature() -> Signature<'static> {
+        SessionInfoExWire::signature()
+    }
+}
+
+impl Serialize for SessionInfoEx {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        (
+            &self.sid,
+            self.uid,
+            &self.user,
+            &self.seat,
+            self.leader,
+            &self.class,
+            &self.tty,
+            self.idle,
+            self.idle_since,
+            &self.path,
+        )
+            .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for SessionInfoEx {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let (sid, uid, user, seat, leader, class, tty, idle, idle_since, path) =
+            SessionInfoExWire::deserialize(deserializer)?;
+        Ok(Self {
+            sid,
+            uid,
+            user,
+            seat,
+            leader,
+            class,
+            tty,
+            idle,
+            idle_since,
+            path,
+            session_type: None,
+        })
+    }
+}
+
+impl SessionInfoEx {
+    /// Fill in what `ListSessions` leaves out from the session's properties
+    pub(crate) fn from_properties(
+        info: SessionInfo,
+        properties: HashMap<String, OwnedValue>,
+    ) -> zbus::Result<Self> {
+        let mut session = Self {
+            sid: info.sid,
+            uid: info.uid,
+            user: info.user,
+            seat: info.seat,
+            leader: 0,
+            class: SessionClass::User,
+            tty: String::new(),
+            idle: false,
+            idle_since: MonotonicStamp::default(),
+            path: info.path,
+            session_type: None,
+        };
+        for (name, value) in properties {
+            match name.as_str() {
+                "Leader" => session.leader = value.try_into()?,
+                "Class" => session.class = value.try_into()?,
+                "TTY" => session.tty = value.try_into()?,
+                "IdleHint" => session.idle = value.try_into()?,
+                "IdleSinceHintMonotonic" => session.idle_since = value.try_into()?,
+                "Type" => session.session_type = Some(value.try_into()?),
+                _ => {}
+            }
+        }
+        Ok(session)
+    }
+
+    pub fn sid(&self) -> &str {
+        &self.sid
+    }
+
+    pub fn uid(&self) -> u32 {
+        self.uid
+    }
+
+    pub fn user(&self) -> &str {
+        &self.user
+    }
+
+    pub fn seat(&self) -> &str {
+        &self.seat
+    }
+
+    pub fn leader(&self) -> u32 {
+        self.leader
+    }
+
+    pub fn class(&self) -> &SessionClass {
+        &self.class
+    }
+
+    pub fn tty(&self) -> &str {
+        &self.tty
+    }
+
+    pub fn is_idle(&self) -> bool {
+        self.idle
+    }
+
+    pub fn idle_since(&self) -> MonotonicStamp {
+        self.idle_since
+    }
+
+    pub fn path(&self) -> &OwnedObjectPath {
+        &self.path
+    }
+
+    /// The session type, which
+    /// [`ManagerProxy::list_sessions_extended`](super::ManagerProxy::list_sessions_extended)
+    /// always reads. `None` from the bare `list_sessions_ex`, whose reply does not have it.
+    pub fn session_type(&self) -> Option<&SessionType> {
+        self.session_type.as_ref()
+    }
+
+    pub(crate) fn set_session_type(&mut self, session_type: SessionType) {
+        self.session_type = Some(session_type);
+    }
+}
+
+impl IntoPath for SessionInfoEx {
+    fn into_path(&self) -> OwnedObjectPath {
+        self.path.clone()
+    }
+
+    fn into_path_ref(&self) -> &OwnedObjectPath {
+        &self.path
+    }
+}
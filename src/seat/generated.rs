@@ -4,7 +4,7 @@
 
 use zbus::dbus_proxy;
 
-use crate::TimeStamp;
+use crate::{MonotonicStamp, RealtimeStamp};
 
 use super::SessionPath;
 
@@ -62,12 +62,12 @@ trait Seat {
     /// IdleSinceHint property
     #[dbus_proxy(property)]
     #[inline]
-    fn idle_since_hint(&self) -> zbus::Result<TimeStamp>;
+    fn idle_since_hint(&self) -> zbus::Result<RealtimeStamp>;
 
     /// IdleSinceHintMonotonic property
     #[dbus_proxy(property)]
     #[inline]
-    fn idle_since_hint_monotonic(&self) -> zbus::Result<TimeStamp>;
+    fn idle_since_hint_monotonic(&self) -> zbus::Result<MonotonicStamp>;
 
     /// Sessions property
     #[dbus_proxy(property)]
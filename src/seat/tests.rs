@@ -2,11 +2,13 @@ use crate::manager::ManagerProxy;
 use crate::manager::ManagerProxyBlocking;
 use crate::seat::SeatProxy;
 use crate::seat::SeatProxyBlocking;
+use crate::testing;
 use futures_lite::future;
 
 #[test]
 fn timestamps() {
-    let connection = zbus::blocking::Connection::system().unwrap();
+    let fake = future::block_on(testing::desktop());
+    let connection = fake.blocking_connection();
     let manager = ManagerProxyBlocking::new(&connection).unwrap();
     let seats = manager.list_seats().unwrap();
     let seat = SeatProxyBlocking::builder(&connection)
@@ -20,7 +22,8 @@ fn timestamps() {
 
 #[test]
 fn properties() {
-    let connection = zbus::blocking::Connection::system().unwrap();
+    let fake = future::block_on(testing::desktop());
+    let connection = fake.blocking_connection();
     let manager = ManagerProxyBlocking::new(&connection).unwrap();
     let seats = manager.list_seats().unwrap();
     let seat = SeatProxyBlocking::builder(&connection)
@@ -42,7 +45,8 @@ fn properties() {
 #[test]
 fn timestamps_async() {
     future::block_on(async {
-        let connection = zbus::Connection::system().await.unwrap();
+        let fake = testing::desktop().await;
+        let connection = fake.connection().clone();
         let manager = ManagerProxy::new(&connection).await.unwrap();
         let seats = manager.list_seats().await.unwrap();
         let seat = SeatProxy::builder(&connection)
@@ -59,7 +63,8 @@ fn timestamps_async() {
 #[test]
 fn properties_async() {
     future::block_on(async {
-        let connection = zbus::Connection::system().await.unwrap();
+        let fake = testing::desktop().await;
+        let connection = fake.connection().clone();
         let manager = ManagerProxy::new(&connection).await.unwrap();
         let seats = manager.list_seats().await.unwrap();
         let seat = SeatProxy::builder(&connection)
@@ -0,0 +1,251 @@
+//! Device access through session control, as used by compositors
+//!
+//! The controller of a session opens input and DRM devices through logind rather
+//! than directly. logind pauses and resumes those devices as the session is
+//! switched away from and back to, which [`SessionController`] keeps track of.
+
+use std::{
+    collections::HashMap,
+    fs, io,
+    os::unix::{
+        fs::{FileTypeExt, MetadataExt},
+        io::{AsFd, BorrowedFd, OwnedFd},
+    },
+    path::Path,
+};
+
+use futures_util::stream::{self, BoxStream, StreamExt};
+
+use crate::{dup_fd, error::Result};
+
+use super::{PauseKind, SessionProxy, SessionProxyBlocking};
+
+/// A device node, identified by its major and minor numbers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct DeviceId {
+    pub major: u32,
+    pub minor: u32,
+}
+
+impl DeviceId {
+    pub fn new(major: u32, minor: u32) -> Self {
+        Self { major, minor }
+    }
+
+    /// Look up the device number of a device node such as `/dev/dri/card0`
+    ///
+    /// Fails with [`io::ErrorKind::InvalidInput`] if the path is not a character or block device.
+    pub fn from_path(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref();
+        let metadata = fs::metadata(path)?;
+        let file_type = metadata.file_type();
+        if !file_type.is_char_device() && !file_type.is_block_device() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} is not a device node", path.display()),
+            ));
+        }
+        let rdev = metadata.rdev();
+        Ok(Self::new(libc::major(rdev), libc::minor(rdev)))
+    }
+}
+
+/// Returned by [`SessionController::next_event`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeviceEvent {
+    /// The device was paused. [`PauseKind::Pause`] has already been acknowledged, and a
+    /// device that is [`PauseKind::Gone`] has been closed.
+    Paused { device: DeviceId, kind: PauseKind },
+    /// The device is usable again, through the new fd logind sent with the resume
+    Resumed { device: DeviceId },
+}
+
+enum Signal {
+    Pause(DeviceId, PauseKind),
+    Resume(DeviceId, OwnedFd),
+}
+
+struct ControlledDevice {
+    fd: OwnedFd,
+    paused: bool,
+}
+
+/// Control of a session and the devices opened through it
+///
+/// Dropping the controller does not give up control: logind keeps the session and its devices
+/// taken until [`release`](Self::release) is called or the D-Bus connection is closed. Call
+/// `release` once done with the session.
+pub struct SessionController<'a> {
+    session: SessionProxy<'a>,
+    devices: HashMap<DeviceId, ControlledDevice>,
+    signals: BoxStream<'a, zbus::Result<Signal>>,
+}
+
+impl<'a> SessionController<'a> {
+    /// Take control of the session. `force` takes it from another controller, which
+    /// requires root.
+    pub async fn take(session: &SessionProxy<'a>, force: bool) -> Result<Self> {
+        let session = session.clone();
+        let pause = session.receive_pause_device().await?.map(|signal| {
+            let args = signal.args()?;
+            let device = DeviceId::new(*args.major(), *args.minor());
+            let kind = PauseKind::from(*args.type_());
+            Ok(Signal::Pause(device, kind))
+        });
+        let resume = session.receive_resume_device().await?.map(|signal| {
+            let args = signal.args()?;
+            let device = DeviceId::new(*args.major(), *args.minor());
+            Ok(Signal::Resume(device, dup_fd(args.fd())?))
+        });
+        let signals = stream::select(pause, resume).boxed();
+        session.take_control(force).await?;
+        Ok(Self {
+            session,
+            devices: HashMap::new(),
+            signals,
+        })
+    }
+
+    pub fn session(&self) -> &SessionProxy<'a> {
+        &self.session
+    }
+
+    /// Open a device node such as `/dev/input/event0` through logind
+    pub async fn open(&mut self, path: impl AsRef<Path>) -> Result<DeviceId> {
+        let id = DeviceId::from_path(path)?;
+        let device = self.session.take_device(id.major, id.minor).await?;
+        let paused = device.inactive();
+        self.devices.insert(
+            id,
+            ControlledDevice {
+                fd: device.into_fd(),
+                paused,
+            },
+        );
+        Ok(id)
+    }
+
+    /// Close a device and hand it back to logind
+    pub async fn close(&mut self, device: DeviceId) -> Result<()> {
+        self.devices.remove(&device);
+        Ok(self
+            .session
+            .release_device(device.major, device.minor)
+            .await?)
+    }
+
+    /// The current fd for a device. This changes each time the device is resumed.
+    pub fn fd(&self, device: DeviceId) -> Option<BorrowedFd<'_>> {
+        self.devices.get(&device).map(|d| d.fd.as_fd())
+    }
+
+    pub fn is_paused(&self, device: DeviceId) -> bool {
+        self.devices.get(&device).is_some_and(|d| d.paused)
+    }
+
+    pub fn devices(&self) -> impl Iterator<Item = DeviceId> + '_ {
+        self.devices.keys().copied()
+    }
+
+    /// Wait for the next pause or resume of one of our devices
+    pub async fn next_event(&mut self) -> Result<DeviceEvent> {
+        loop {
+            let signal = self
+                .signals
+                .next()
+                .await
+                .ok_or_else(|| zbus::Error::Failure("device signal stream ended".into()))??;
+            match signal {
+                Signal::Pause(device, kind) => {
+                    if kind == PauseKind::Pause {
+                        // logind waits for this even for devices we don't know about
+                        self.session
+                            .pause_device_complete(device.major, device.minor)
+                            .await?;
+                    }
+                    if kind == PauseKind::Gone {
+                        if self.devices.remove(&device).is_none() {
+                            continue;
+                        }
+                    } else if let Some(d) = self.devices.get_mut(&device) {
+                        d.paused = true;
+                    } else {
+                        continue;
+                    }
+                    return Ok(DeviceEvent::Paused { device, kind });
+                }
+                Signal::Resume(device, fd) => {
+                    if let Some(d) = self.devices.get_mut(&device) {
+                        d.fd = fd;
+                        d.paused = false;
+                        return Ok(DeviceEvent::Resumed { device });
+                    }
+                }
+            }
+        }
+    }
+
+    /// Give up control of the session. All devices are closed.
+    pub async fn release(self) -> Result<()> {
+        Ok(self.session.release_control().await?)
+    }
+}
+
+/// Blocking version of [`SessionController`]
+///
+/// As with `SessionController`, dropping it keeps the session controlled until
+/// [`release`](Self::release) is called or the connection is closed.
+pub struct SessionControllerBlocking<'a> {
+    inner: SessionController<'a>,
+    session: SessionProxyBlocking<'a>,
+}
+
+impl<'a> SessionControllerBlocking<'a> {
+    /// Take control of the session. `force` takes it from another controller, which
+    /// requires root.
+    pub fn take(session: &SessionProxyBlocking<'a>, force: bool) -> Result<Self> {
+        let proxy = SessionProxy::from(session.inner().inner().clone());
+        let inner = zbus::block_on(SessionController::take(&proxy, force))?;
+        Ok(Self {
+            inner,
+            session: session.clone(),
+        })
+    }
+
+    pub fn session(&self) -> &SessionProxyBlocking<'a> {
+        &self.session
+    }
+
+    /// Open a device node such as `/dev/input/event0` through logind
+    pub fn open(&mut self, path: impl AsRef<Path>) -> Result<DeviceId> {
+        zbus::block_on(self.inner.open(path))
+    }
+
+    /// Close a device and hand it back to logind
+    pub fn close(&mut self, device: DeviceId) -> Result<()> {
+        zbus::block_on(self.inner.close(device))
+    }
+
+    /// The current fd for a device. This changes each time the device is resumed.
+    pub fn fd(&self, device: DeviceId) -> Option<BorrowedFd<'_>> {
+        self.inner.fd(device)
+    }
+
+    pub fn is_paused(&self, device: DeviceId) -> bool {
+        self.inner.is_paused(device)
+    }
+
+    pub fn devices(&self) -> impl Iterator<Item = DeviceId> + '_ {
+        self.inner.devices()
+    }
+
+    /// Wait for the next pause or resume of one of our devices
+    pub fn next_event(&mut self) -> Result<DeviceEvent> {
+        zbus::block_on(self.inner.next_event())
+    }
+
+    /// Give up control of the session. All devices are closed.
+    pub fn release(self) -> Result<()> {
+        zbus::block_on(self.inner.release())
+    }
+}
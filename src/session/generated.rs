@@ -2,9 +2,9 @@
 
 #![allow(non_snake_case)]
 
-use zbus::dbus_proxy;
+use zbus::{dbus_proxy, zvariant};
 
-use crate::{SomePath, TimeStamp};
+use crate::{MonotonicStamp, RealtimeStamp, SomePath};
 
 use super::{Device, SessionClass, SessionState, SessionType, User};
 
@@ -83,12 +83,7 @@ trait Session {
     /// ResumeDevice signal
     #[dbus_proxy(signal)]
     #[inline]
-    fn resume_device(
-        &self,
-        major: u32,
-        minor: u32,
-        fd: std::os::unix::io::RawFd,
-    ) -> zbus::Result<()>;
+    fn resume_device(&self, major: u32, minor: u32, fd: zvariant::Fd) -> zbus::Result<()>;
 
     /// Unlock signal
     #[dbus_proxy(signal)]
@@ -133,12 +128,12 @@ trait Session {
     /// IdleSinceHint property
     #[dbus_proxy(property)]
     #[inline]
-    fn idle_since_hint(&self) -> zbus::Result<TimeStamp>;
+    fn idle_since_hint(&self) -> zbus::Result<RealtimeStamp>;
 
     /// IdleSinceHintMonotonic property
     #[dbus_proxy(property)]
     #[inline]
-    fn idle_since_hint_monotonic(&self) -> zbus::Result<TimeStamp>;
+    fn idle_since_hint_monotonic(&self) -> zbus::Result<MonotonicStamp>;
 
     /// Leader property
     #[dbus_proxy(property)]
@@ -198,12 +193,12 @@ trait Session {
     /// Timestamp property
     #[dbus_proxy(property)]
     #[inline]
-    fn timestamp(&self) -> zbus::Result<TimeStamp>;
+    fn timestamp(&self) -> zbus::Result<RealtimeStamp>;
 
     /// TimestampMonotonic property
     #[dbus_proxy(property)]
     #[inline]
-    fn timestamp_monotonic(&self) -> zbus::Result<TimeStamp>;
+    fn timestamp_monotonic(&self) -> zbus::Result<MonotonicStamp>;
 
     /// Type property
     #[dbus_proxy(property)]
@@ -1,8 +1,10 @@
+mod controller;
 mod generated;
 mod types;
 
 #[cfg(test)]
 mod tests;
 
+pub use controller::*;
 pub use generated::*;
 pub use types::*;
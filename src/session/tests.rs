@@ -2,13 +2,19 @@ use crate::manager::ManagerProxy;
 use crate::manager::ManagerProxyBlocking;
 use crate::session::SessionProxy;
 use crate::session::SessionProxyBlocking;
+use crate::testing;
 use futures_lite::future;
+use std::time::{Instant, SystemTime};
 
-use super::SessionType;
+use super::{
+    DeviceEvent, DeviceId, PauseKind, SessionClass, SessionController, SessionControllerBlocking,
+    SessionType,
+};
 
 #[test]
 fn timestamps() {
-    let connection = zbus::blocking::Connection::system().unwrap();
+    let fake = future::block_on(testing::desktop());
+    let connection = fake.blocking_connection();
     let manager = ManagerProxyBlocking::new(&connection).unwrap();
     let sessions = manager.list_sessions().unwrap();
     let session = SessionProxyBlocking::builder(&connection)
@@ -18,15 +24,21 @@ fn timestamps() {
         .unwrap();
 
     let time1 = session.timestamp().unwrap();
-    assert!(time1.as_secs() > 0);
+    assert!(time1.to_system_time().unwrap() <= SystemTime::now());
 
     let time2 = session.timestamp_monotonic().unwrap();
-    assert!(time2.as_secs() > 0);
+    assert!(time2.to_instant().unwrap() <= Instant::now());
+    assert!(time2.elapsed().unwrap() > time1.elapsed().unwrap());
+
+    // Zero means the session was never idle
+    assert_eq!(session.idle_since_hint().unwrap().to_system_time(), None);
+    assert_eq!(session.idle_since_hint_monotonic().unwrap().elapsed(), None);
 }
 
 #[test]
 fn list_active_session_types() {
-    let connection = zbus::blocking::Connection::system().unwrap();
+    let fake = future::block_on(testing::desktop());
+    let connection = fake.blocking_connection();
     let manager = ManagerProxyBlocking::new(&connection).unwrap();
     let sessions = manager.list_sessions().unwrap();
 
@@ -43,9 +55,11 @@ fn list_active_session_types() {
                 SessionType::Wayland => assert_eq!(st, SessionType::Wayland),
                 SessionType::MIR => assert_eq!(st, SessionType::MIR),
                 SessionType::TTY => assert_eq!(st, SessionType::TTY),
+                SessionType::Web => assert_eq!(st, SessionType::Web),
                 SessionType::Unspecified => {
                     assert_eq!(st, SessionType::Unspecified)
                 }
+                SessionType::Other(_) => assert!(!st.as_str().is_empty()),
             }
         }
     }
@@ -53,7 +67,8 @@ fn list_active_session_types() {
 
 #[test]
 fn properties() {
-    let connection = zbus::blocking::Connection::system().unwrap();
+    let fake = future::block_on(testing::desktop());
+    let connection = fake.blocking_connection();
     let manager = ManagerProxyBlocking::new(&connection).unwrap();
     let sessions = manager.list_sessions().unwrap();
     let session = SessionProxyBlocking::builder(&connection)
@@ -92,7 +107,8 @@ fn properties() {
 #[test]
 fn timestamps_async() {
     future::block_on(async {
-        let connection = zbus::Connection::system().await.unwrap();
+        let fake = testing::desktop().await;
+        let connection = fake.connection().clone();
         let manager = ManagerProxy::new(&connection).await.unwrap();
         let sessions = manager.list_sessions().await.unwrap();
         let session = SessionProxy::builder(&connection)
@@ -103,17 +119,18 @@ fn timestamps_async() {
             .unwrap();
 
         let time1 = session.timestamp().await.unwrap();
-        assert!(time1.as_secs() > 0);
+        assert!(time1.to_system_time().unwrap() <= SystemTime::now());
 
         let time2 = session.timestamp_monotonic().await.unwrap();
-        assert!(time2.as_secs() > 0);
+        assert!(time2.to_instant().unwrap() <= Instant::now());
     })
 }
 
 #[test]
 fn list_active_session_types_async() {
     future::block_on(async {
-        let connection = zbus::Connection::system().await.unwrap();
+        let fake = testing::desktop().await;
+        let connection = fake.connection().clone();
         let manager = ManagerProxy::new(&connection).await.unwrap();
         let sessions = manager.list_sessions().await.unwrap();
 
@@ -131,9 +148,11 @@ fn list_active_session_types_async() {
                     SessionType::Wayland => assert_eq!(st, SessionType::Wayland),
                     SessionType::MIR => assert_eq!(st, SessionType::MIR),
                     SessionType::TTY => assert_eq!(st, SessionType::TTY),
+                    SessionType::Web => assert_eq!(st, SessionType::Web),
                     SessionType::Unspecified => {
                         assert_eq!(st, SessionType::Unspecified)
                     }
+                    SessionType::Other(_) => assert!(!st.as_str().is_empty()),
                 }
             }
         }
@@ -143,7 +162,8 @@ fn list_active_session_types_async() {
 #[test]
 fn properties_async() {
     future::block_on(async {
-        let connection = zbus::Connection::system().await.unwrap();
+        let fake = testing::desktop().await;
+        let connection = fake.connection().clone();
         let manager = ManagerProxy::new(&connection).await.unwrap();
         let sessions = manager.list_sessions().await.unwrap();
         let session = SessionProxy::builder(&connection)
@@ -180,3 +200,128 @@ fn properties_async() {
         assert!(session.VTNr().await.is_ok());
     })
 }
+
+#[test]
+fn session_controller() {
+    future::block_on(async {
+        let fake = testing::desktop().await;
+        let session = SessionProxy::builder(fake.connection())
+            .path("/org/freedesktop/login1/session/_32")
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+
+        // TakeDevice needs control of the session
+        assert!(session.take_device(1, 3).await.is_err());
+
+        let mut controller = SessionController::take(&session, false).await.unwrap();
+        let device = controller.open("/dev/null").await.unwrap();
+        assert_eq!(device, DeviceId::new(1, 3));
+        assert!(!controller.is_paused(device));
+        assert!(controller.fd(device).is_some());
+        assert_eq!(fake.taken_devices(), vec![("2".to_string(), 1, 3)]);
+        let err = controller.open("/dev/null").await.unwrap_err();
+        assert!(matches!(err, crate::Error::DeviceIsTaken(_)), "{err:?}");
+        // Not device nodes, so TakeDevice is never sent
+        let err = DeviceId::from_path("/dev").unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        let exe = std::env::current_exe().unwrap();
+        let err = DeviceId::from_path(&exe).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        assert!(controller.open(&exe).await.is_err());
+        assert_eq!(fake.taken_devices().len(), 1);
+
+        fake.pause_device("2", 1, 3, "pause").await.unwrap();
+        let event = controller.next_event().await.unwrap();
+        assert_eq!(
+            event,
+            DeviceEvent::Paused {
+                device,
+                kind: PauseKind::Pause
+            }
+        );
+        assert!(controller.is_paused(device));
+        assert!(fake
+            .calls()
+            .iter()
+            .any(|call| call.member == "PauseDeviceComplete"));
+
+        fake.resume_device("2", 1, 3).await.unwrap();
+        let event = controller.next_event().await.unwrap();
+        assert_eq!(event, DeviceEvent::Resumed { device });
+        assert!(!controller.is_paused(device));
+
+        fake.pause_device("2", 1, 3, "gone").await.unwrap();
+        let event = controller.next_event().await.unwrap();
+        assert_eq!(
+            event,
+            DeviceEvent::Paused {
+                device,
+                kind: PauseKind::Gone
+            }
+        );
+        assert!(controller.fd(device).is_none());
+
+        controller.release().await.unwrap();
+        assert!(fake.taken_devices().is_empty());
+    })
+}
+
+#[test]
+fn session_controller_blocking() {
+    let fake = future::block_on(testing::desktop());
+    let connection = fake.blocking_connection();
+    let session = SessionProxyBlocking::builder(&connection)
+        .path("/org/freedesktop/login1/session/_32")
+        .unwrap()
+        .build()
+        .unwrap();
+
+    let mut controller = SessionControllerBlocking::take(&session, false).unwrap();
+    let device = controller.open("/dev/null").unwrap();
+    assert_eq!(controller.session().path(), session.path());
+
+    future::block_on(fake.pause_device("2", 1, 3, "force")).unwrap();
+    let event = controller.next_event().unwrap();
+    assert_eq!(
+        event,
+        DeviceEvent::Paused {
+            device,
+            kind: PauseKind::Force
+        }
+    );
+    // Forced pauses are not acknowledged
+    assert!(!fake
+        .calls()
+        .iter()
+        .any(|call| call.member == "PauseDeviceComplete"));
+
+    controller.close(device).unwrap();
+    assert!(fake.taken_devices().is_empty());
+    assert_eq!(controller.devices().count(), 0);
+}
+
+#[test]
+fn unknown_values() {
+    let fake = future::block_on(testing::desktop());
+    future::block_on(fake.update_session("2", |s| {
+        s.class = SessionClass::Other("holodeck".to_string());
+        s.type_ = SessionType::Web;
+    }))
+    .unwrap();
+    let connection = fake.blocking_connection();
+    let session = SessionProxyBlocking::builder(&connection)
+        .path("/org/freedesktop/login1/session/_32")
+        .unwrap()
+        .build()
+        .unwrap();
+
+    assert_eq!(
+        session.class().unwrap(),
+        SessionClass::Other("holodeck".to_string())
+    );
+    assert_eq!(session.type_().unwrap(), SessionType::Web);
+    assert!(session.set_type("web").is_ok());
+    assert!(session.set_type("holodeck").is_err());
+}
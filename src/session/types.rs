@@ -1,9 +1,11 @@
 use serde::{Deserialize, Serialize};
+use std::os::unix::io::{AsFd, AsRawFd, BorrowedFd, OwnedFd, RawFd};
 use std::str::FromStr;
-use zbus::fdo;
-use zbus::zvariant::{OwnedObjectPath, OwnedValue, Structure, Type};
+use zbus::zvariant::{self, OwnedObjectPath, OwnedValue, Signature, Structure, Type};
 
-use crate::{enum_impl_serde_str, enum_impl_str_conv, impl_try_from_owned_as_str, IntoPath};
+use crate::{
+    dup_fd, enum_impl_serde_str, enum_impl_str_conv, impl_try_from_owned_as_str, IntoPath,
+};
 
 #[derive(Debug, PartialEq, Eq, Clone, Type, Serialize, Deserialize)]
 pub struct User {
@@ -45,14 +47,18 @@ impl IntoPath for User {
 }
 
 /// The type of Session
-#[derive(Debug, PartialEq, Eq, Clone, Copy, Type)]
+#[derive(Debug, PartialEq, Eq, Clone, Type)]
 #[zvariant(signature = "s")]
 pub enum SessionType {
     X11,
     Wayland,
     MIR,
     TTY,
+    /// A web session, such as one started by Cockpit
+    Web,
     Unspecified,
+    /// A value not known to this crate, as sent by logind
+    Other(String),
 }
 enum_impl_serde_str!(SessionType);
 impl_try_from_owned_as_str!(SessionType);
@@ -61,48 +67,128 @@ enum_impl_str_conv!(SessionType, {
     "x11": X11,
     "mir": MIR,
     "tty": TTY,
+    "web": Web,
     "unspecified": Unspecified,
 });
 
-#[derive(Debug, PartialEq, Eq, Type, Serialize, Deserialize)]
+/// A device opened with [`SessionProxy::take_device`](super::SessionProxy::take_device)
+#[derive(Debug)]
 pub struct Device {
-    file_descriptor: std::os::unix::io::RawFd,
+    file_descriptor: OwnedFd,
     inactive: bool,
 }
 
 impl Device {
-    pub fn file_descriptor(&self) -> std::os::unix::io::RawFd {
-        self.file_descriptor
+    pub fn file_descriptor(&self) -> RawFd {
+        self.file_descriptor.as_raw_fd()
     }
 
     pub fn inactive(&self) -> bool {
         self.inactive
     }
+
+    pub fn into_fd(self) -> OwnedFd {
+        self.file_descriptor
+    }
+}
+
+impl AsFd for Device {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.file_descriptor.as_fd()
+    }
+}
+
+impl Type for Device {
+    fn signature() -> Signature<'static> {
+        Signature::from_static_str_unchecked("(hb)")
+    }
 }
 
+impl<'de> Deserialize<'de> for Device {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let (fd, inactive) = <(zvariant::Fd, bool)>::deserialize(deserializer)?;
+        Ok(Self {
+            file_descriptor: dup_fd(&fd).map_err(serde::de::Error::custom)?,
+            inactive,
+        })
+    }
+}
+
+/// Why a device was paused, as sent with the `PauseDevice` signal
+#[derive(Debug, PartialEq, Eq, Clone, Type)]
+#[zvariant(signature = "s")]
+pub enum PauseKind {
+    /// The session is being deactivated. The pause must be acknowledged with
+    /// `PauseDeviceComplete` before logind moves on.
+    Pause,
+    /// The device was already paused without waiting for the controller
+    Force,
+    /// The device was removed and its fd is no longer usable
+    Gone,
+    /// A value not known to this crate, as sent by logind
+    Other(String),
+}
+enum_impl_serde_str!(PauseKind);
+enum_impl_str_conv!(PauseKind, {
+    "pause": Pause,
+    "force": Force,
+    "gone": Gone,
+});
+
 /// Class of Session
-#[derive(Debug, PartialEq, Eq, Clone, Copy, Type)]
+#[derive(Debug, PartialEq, Eq, Clone, Type)]
 #[zvariant(signature = "s")]
 pub enum SessionClass {
     User,
+    /// A user session started before logins are generally allowed, e.g. for emergency access
+    UserEarly,
+    /// A user session that has not finished being set up
+    UserIncomplete,
+    /// A user session without a service manager for the user
+    UserLight,
+    /// Like `UserEarly`, without a service manager for the user
+    UserEarlyLight,
     Greeter,
     LockScreen,
+    /// A session that is not interactive, such as a cron job
+    Background,
+    /// Like `Background`, without a service manager for the user
+    BackgroundLight,
+    /// The session of a user's service manager
+    Manager,
+    /// Like `Manager`, started before logins are generally allowed
+    ManagerEarly,
+    /// A value not known to this crate, as sent by logind
+    Other(String),
 }
 enum_impl_serde_str!(SessionClass);
 impl_try_from_owned_as_str!(SessionClass);
 enum_impl_str_conv!(SessionClass, {
     "user": User,
+    "user-early": UserEarly,
+    "user-incomplete": UserIncomplete,
+    "user-light": UserLight,
+    "user-early-light": UserEarlyLight,
     "greeter": Greeter,
     "lock-screen": LockScreen,
+    "background": Background,
+    "background-light": BackgroundLight,
+    "manager": Manager,
+    "manager-early": ManagerEarly,
 });
 
 /// State of a session
-#[derive(Debug, PartialEq, Eq, Clone, Copy, Type)]
+#[derive(Debug, PartialEq, Eq, Clone, Type)]
 #[zvariant(signature = "s")]
 pub enum SessionState {
     Online,
     Active,
     Closing,
+    /// A value not known to this crate, as sent by logind
+    Other(String),
 }
 enum_impl_serde_str!(SessionState);
 impl_try_from_owned_as_str!(SessionState);
@@ -0,0 +1,630 @@
+//! A local copy of logind's sessions, seats and users, kept current by its signals
+//!
+//! [`LogindState`] reads everything once and then follows [`LogindEvents`], so questions
+//! like "which session is active on seat0" are answered without a round trip.
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    sync::{Arc, Mutex, RwLock},
+};
+
+use futures_util::StreamExt;
+use zbus::{
+    fdo::{self, PropertiesProxy},
+    names::InterfaceName,
+    zvariant::{OwnedObjectPath, OwnedValue},
+    Connection,
+};
+
+use crate::{
+    events::{LogindEvent, LogindEvents, LogindObject, DESTINATION},
+    seat::SessionPath,
+    session::{SessionClass, SessionState, SessionType, User},
+    user::UserState,
+    SomePath,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// What changed in a [`LogindState`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum StateChange {
+    Added(LogindObject),
+    Removed(LogindObject),
+    /// One or more properties of the object changed
+    Changed(LogindObject),
+}
+
+/// A session as last seen
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionSnapshot {
+    id: String,
+    path: OwnedObjectPath,
+    uid: u32,
+    name: String,
+    seat: Option<String>,
+    class: SessionClass,
+    type_: SessionType,
+    state: SessionState,
+    active: bool,
+    remote: bool,
+    idle_hint: bool,
+    locked_hint: bool,
+    tty: String,
+    vtnr: u32,
+    display: String,
+    desktop: String,
+    service: String,
+    leader: u32,
+}
+
+impl SessionSnapshot {
+    fn new(id: String, path: OwnedObjectPath) -> Self {
+        Self {
+            id,
+            path,
+            uid: 0,
+            name: String::new(),
+            seat: None,
+            class: SessionClass::User,
+            type_: SessionType::Unspecified,
+            state: SessionState::Online,
+            active: false,
+            remote: false,
+            idle_hint: false,
+            locked_hint: false,
+            tty: String::new(),
+            vtnr: 0,
+            display: String::new(),
+            desktop: String::new(),
+            service: String::new(),
+            leader: 0,
+        }
+    }
+
+    fn apply(&mut self, name: &str, value: OwnedValue) -> zbus::Result<()> {
+        match name {
+            "User" => self.uid = User::try_from(value)?.uid(),
+            "Name" => self.name = value.try_into()?,
+            "Seat" => self.seat = non_empty(SomePath::try_from(value)?.id()),
+            "Class" => self.class = value.try_into()?,
+            "Type" => self.type_ = value.try_into()?,
+            "State" => self.state = value.try_into()?,
+            "Active" => self.active = value.try_into()?,
+            "Remote" => self.remote = value.try_into()?,
+            "IdleHint" => self.idle_hint = value.try_into()?,
+            "LockedHint" => self.locked_hint = value.try_into()?,
+            "TTY" => self.tty = value.try_into()?,
+            "VTNr" => self.vtnr = value.try_into()?,
+            "Display" => self.display = value.try_into()?,
+            "Desktop" => self.desktop = value.try_into()?,
+            "Service" => self.service = value.try_into()?,
+            "Leader" => self.leader = value.try_into()?,
+            _ => {}
+        }
+        Ok(())
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn path(&self) -> &OwnedObjectPath {
+        &self.path
+    }
+
+    pub fn uid(&self) -> u32 {
+        self.uid
+    }
+
+    /// Name of the session user
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn seat(&self) -> Option<&str> {
+        self.seat.as_deref()
+    }
+
+    pub fn class(&self) -> &SessionClass {
+        &self.class
+    }
+
+    pub fn session_type(&self) -> &SessionType {
+        &self.type_
+    }
+
+    pub fn state(&self) -> &SessionState {
+        &self.state
+    }
+
+    pub fn active(&self) -> bool {
+        self.active
+    }
+
+    pub fn remote(&self) -> bool {
+        self.remote
+    }
+
+    pub fn idle_hint(&self) -> bool {
+        self.idle_hint
+    }
+
+    pub fn locked_hint(&self) -> bool {
+        self.locked_hint
+    }
+
+    pub fn tty(&self) -> &str {
+        &self.tty
+    }
+
+    pub fn vtnr(&self) -> u32 {
+        self.vtnr
+    }
+
+    pub fn display(&self) -> &str {
+        &self.display
+    }
+
+    pub fn desktop(&self) -> &str {
+        &self.desktop
+    }
+
+    pub fn service(&self) -> &str {
+        &self.service
+    }
+
+    /// PID of the session leader
+    pub fn leader(&self) -> u32 {
+        self.leader
+    }
+}
+
+/// A seat as last seen
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SeatSnapshot {
+    id: String,
+    path: OwnedObjectPath,
+    active_session: Option<String>,
+    can_graphical: bool,
+    can_tty: bool,
+    idle_hint: bool,
+}
+
+impl SeatSnapshot {
+    fn new(id: String, path: OwnedObjectPath) -> Self {
+        Self {
+            id,
+            path,
+            active_session: None,
+            can_graphical: false,
+            can_tty: false,
+            idle_hint: false,
+        }
+    }
+
+    fn apply(&mut self, name: &str, value: OwnedValue) -> zbus::Result<()> {
+        match name {
+            "ActiveSession" => self.active_session = non_empty(SessionPath::try_from(value)?.id()),
+            "CanGraphical" => self.can_graphical = value.try_into()?,
+            "CanTTY" => self.can_tty = value.try_into()?,
+            "IdleHint" => self.idle_hint = value.try_into()?,
+            _ => {}
+        }
+        Ok(())
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn path(&self) -> &OwnedObjectPath {
+        &self.path
+    }
+
+    pub fn active_session(&self) -> Option<&str> {
+        self.active_session.as_deref()
+    }
+
+    pub fn can_graphical(&self) -> bool {
+        self.can_graphical
+    }
+
+    pub fn can_tty(&self) -> bool {
+        self.can_tty
+    }
+
+    pub fn idle_hint(&self) -> bool {
+        self.idle_hint
+    }
+}
+
+/// A user as last seen
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserSnapshot {
+    uid: u32,
+    path: OwnedObjectPath,
+    name: String,
+    state: UserState,
+    display: Option<String>,
+    linger: bool,
+    idle_hint: bool,
+    runtime_path: String,
+}
+
+impl UserSnapshot {
+    fn new(uid: u32, path: OwnedObjectPath) -> Self {
+        Self {
+            uid,
+            path,
+            name: String::new(),
+            state: UserState::Offline,
+            display: None,
+            linger: false,
+            idle_hint: false,
+            runtime_path: String::new(),
+        }
+    }
+
+    fn apply(&mut self, name: &str, value: OwnedValue) -> zbus::Result<()> {
+        match name {
+            "Name" => self.name = value.try_into()?,
+            "State" => self.state = value.try_into()?,
+            "Display" => self.display = non_empty(SomePath::try_from(value)?.id()),
+            "Linger" => self.linger = value.try_into()?,
+            "IdleHint" => self.idle_hint = value.try_into()?,
+            "RuntimePath" => self.runtime_path = value.try_into()?,
+            _ => {}
+        }
+        Ok(())
+    }
+
+    pub fn uid(&self) -> u32 {
+        self.uid
+    }
+
+    pub fn path(&self) -> &OwnedObjectPath {
+        &self.path
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn state(&self) -> &UserState {
+        &self.state
+    }
+
+    /// The session the user's graphical display runs in
+    pub fn display(&self) -> Option<&str> {
+        self.display.as_deref()
+    }
+
+    pub fn linger(&self) -> bool {
+        self.linger
+    }
+
+    pub fn idle_hint(&self) -> bool {
+        self.idle_hint
+    }
+
+    pub fn runtime_path(&self) -> &str {
+        &self.runtime_path
+    }
+}
+
+/// logind sends an empty ID for "none"
+fn non_empty(id: &str) -> Option<String> {
+    (!id.is_empty()).then(|| id.to_string())
+}
+
+#[derive(Debug, Default)]
+struct Cache {
+    sessions: BTreeMap<String, SessionSnapshot>,
+    seats: BTreeMap<String, SeatSnapshot>,
+    users: BTreeMap<u32, UserSnapshot>,
+    /// Objects with a property value that did not parse, read again on their next change
+    stale: HashSet<LogindObject>,
+}
+
+impl Cache {
+    /// Add `object` with all its properties, replacing what was cached for it
+    fn insert(
+        &mut self,
+        object: &LogindObject,
+        path: &OwnedObjectPath,
+        properties: HashMap<String, OwnedValue>,
+    ) {
+        let mut applied = Ok(());
+        match object {
+            LogindObject::Session(id) => {
+                let mut session = SessionSnapshot::new(id.clone(), path.clone());
+                for (name, value) in properties {
+                    applied = applied.and(session.apply(&name, value));
+                }
+                self.sessions.insert(id.clone(), session);
+            }
+            LogindObject::Seat(id) => {
+                let mut seat = SeatSnapshot::new(id.clone(), path.clone());
+                for (name, value) in properties {
+                    applied = applied.and(seat.apply(&name, value));
+                }
+                self.seats.insert(id.clone(), seat);
+            }
+            LogindObject::User(uid) => {
+                let mut user = UserSnapshot::new(*uid, path.clone());
+                for (name, value) in properties {
+                    applied = applied.and(user.apply(&name, value));
+                }
+                self.users.insert(*uid, user);
+            }
+            LogindObject::Manager => return,
+        }
+        if applied.is_err() {
+            self.stale.insert(object.clone());
+        } else {
+            self.stale.remove(object);
+        }
+    }
+
+    fn remove(&mut self, object: &LogindObject) -> bool {
+        self.stale.remove(object);
+        match object {
+            LogindObject::Session(id) => self.sessions.remove(id).is_some(),
+            LogindObject::Seat(id) => self.seats.remove(id).is_some(),
+            LogindObject::User(uid) => self.users.remove(uid).is_some(),
+            LogindObject::Manager => false,
+        }
+    }
+
+    /// Take over the objects of `other`, which were read after those cached here
+    fn merge(&mut self, mut other: Cache) {
+        let read = other
+            .sessions
+            .keys()
+            .cloned()
+            .map(LogindObject::Session)
+            .chain(other.seats.keys().cloned().map(LogindObject::Seat))
+            .chain(other.users.keys().copied().map(LogindObject::User));
+        for object in read {
+            self.stale.remove(&object);
+        }
+        self.sessions.append(&mut other.sessions);
+        self.seats.append(&mut other.seats);
+        self.users.append(&mut other.users);
+        self.stale.extend(other.stale);
+    }
+}
+
+#[derive(Debug, Default)]
+struct Shared {
+    cache: RwLock<Cache>,
+    subscribers: Mutex<Vec<async_channel::Sender<StateChange>>>,
+}
+
+impl Shared {
+    fn notify(&self, change: StateChange) {
+        self.subscribers
+            .lock()
+            .unwrap()
+            .retain(|subscriber| subscriber.try_send(change.clone()).is_ok());
+    }
+}
+
+/// Sessions, seats and users of logind, updated in the background from its signals
+///
+/// Clones share the same state. The updates run on the connection's executor and stop once
+/// the last clone is dropped.
+#[derive(Debug, Clone)]
+pub struct LogindState {
+    shared: Arc<Shared>,
+    _task: Arc<zbus::Task<()>>,
+}
+
+impl LogindState {
+    pub async fn new(connection: &Connection) -> zbus::Result<Self> {
+        // The events list what exists after they start listening, so nothing created in
+        // between is missed
+        let (mut events, listed) = LogindEvents::with_listing(connection).await?;
+        let mut cache = Cache::default();
+        for (object, path) in &listed {
+            fetch_listed(connection, &mut cache, object, path).await?;
+        }
+
+        let shared = Arc::new(Shared {
+            cache: RwLock::new(cache),
+            subscribers: Mutex::default(),
+        });
+        let task = {
+            let connection = connection.clone();
+            let shared = shared.clone();
+            async move {
+                while let Some(event) = events.next().await {
+                    update(&connection, &shared, event).await;
+                }
+            }
+        };
+        let task = connection.executor().spawn(task, "logind state");
+        Ok(Self {
+            shared,
+            _task: Arc::new(task),
+        })
+    }
+
+    pub fn new_blocking(connection: &zbus::blocking::Connection) -> zbus::Result<Self> {
+        zbus::block_on(Self::new(connection.inner()))
+    }
+
+    /// A channel receiving every change from now on
+    pub fn changes(&self) -> async_channel::Receiver<StateChange> {
+        let (sender, receiver) = async_channel::unbounded();
+        self.shared.subscribers.lock().unwrap().push(sender);
+        receiver
+    }
+
+    pub fn session(&self, id: &str) -> Option<SessionSnapshot> {
+        self.cache().sessions.get(id).cloned()
+    }
+
+    pub fn sessions(&self) -> Vec<SessionSnapshot> {
+        self.cache().sessions.values().cloned().collect()
+    }
+
+    /// Sessions of the user `uid`
+    pub fn sessions_of(&self, uid: u32) -> Vec<SessionSnapshot> {
+        self.cache()
+            .sessions
+            .values()
+            .filter(|session| session.uid == uid)
+            .cloned()
+            .collect()
+    }
+
+    /// The active session on `seat_id`, if any
+    pub fn active_session(&self, seat_id: &str) -> Option<SessionSnapshot> {
+        let cache = self.cache();
+        let id = cache.seats.get(seat_id)?.active_session.as_deref()?;
+        cache.sessions.get(id).cloned()
+    }
+
+    pub fn seat(&self, id: &str) -> Option<SeatSnapshot> {
+        self.cache().seats.get(id).cloned()
+    }
+
+    pub fn seats(&self) -> Vec<SeatSnapshot> {
+        self.cache().seats.values().cloned().collect()
+    }
+
+    pub fn user(&self, uid: u32) -> Option<UserSnapshot> {
+        self.cache().users.get(&uid).cloned()
+    }
+
+    pub fn users(&self) -> Vec<UserSnapshot> {
+        self.cache().users.values().cloned().collect()
+    }
+
+    fn cache(&self) -> std::sync::RwLockReadGuard<'_, Cache> {
+        self.shared.cache.read().unwrap()
+    }
+}
+
+async fn update(connection: &Connection, shared: &Shared, event: LogindEvent) {
+    let change = match event {
+        LogindEvent::SessionNew { id, path } => {
+            added(connection, shared, LogindObject::Session(id), &path).await
+        }
+        LogindEvent::SeatNew { id, path } => {
+            added(connection, shared, LogindObject::Seat(id), &path).await
+        }
+        LogindEvent::UserNew { uid, path } => {
+            added(connection, shared, LogindObject::User(uid), &path).await
+        }
+        LogindEvent::SessionRemoved { id, .. } => removed(shared, LogindObject::Session(id)),
+        LogindEvent::SeatRemoved { id, .. } => removed(shared, LogindObject::Seat(id)),
+        LogindEvent::UserRemoved { uid, .. } => removed(shared, LogindObject::User(uid)),
+        LogindEvent::PropertiesChanged {
+            object,
+            changed,
+            invalidated,
+        } => changed_properties(connection, shared, object, changed, invalidated).await,
+        _ => None,
+    };
+    if let Some(change) = change {
+        shared.notify(change);
+    }
+}
+
+async fn added(
+    connection: &Connection,
+    shared: &Shared,
+    object: LogindObject,
+    path: &OwnedObjectPath,
+) -> Option<StateChange> {
+    let mut cache = Cache::default();
+    fetch(connection, &mut cache, &object, path).await.ok()?;
+    shared.cache.write().unwrap().merge(cache);
+    Some(StateChange::Added(object))
+}
+
+fn removed(shared: &Shared, object: LogindObject) -> Option<StateChange> {
+    let removed = shared.cache.write().unwrap().remove(&object);
+    removed.then_some(StateChange::Removed(object))
+}
+
+async fn changed_properties(
+    connection: &Connection,
+    shared: &Shared,
+    object: LogindObject,
+    changed: HashMap<String, OwnedValue>,
+    invalidated: Vec<String>,
+) -> Option<StateChange> {
+    // Only the names of invalidated properties were sent
+    let mut stale = !invalidated.is_empty();
+    let path = {
+        let mut cache = shared.cache.write().unwrap();
+        stale |= cache.stale.contains(&object);
+        for (name, value) in changed {
+            let applied = match &object {
+                LogindObject::Session(id) => cache.sessions.get_mut(id)?.apply(&name, value),
+                LogindObject::Seat(id) => cache.seats.get_mut(id)?.apply(&name, value),
+                LogindObject::User(uid) => cache.users.get_mut(uid)?.apply(&name, value),
+                LogindObject::Manager => return None,
+            };
+            // A value that did not parse leaves the old one, until it is read again
+            stale |= applied.is_err();
+        }
+        match &object {
+            LogindObject::Session(id) => cache.sessions.get(id)?.path.clone(),
+            LogindObject::Seat(id) => cache.seats.get(id)?.path.clone(),
+            LogindObject::User(uid) => cache.users.get(uid)?.path.clone(),
+            LogindObject::Manager => return None,
+        }
+    };
+    if stale {
+        let mut cache = Cache::default();
+        if fetch(connection, &mut cache, &object, &path).await.is_ok() {
+            shared.cache.write().unwrap().merge(cache);
+        }
+    }
+    Some(StateChange::Changed(object))
+}
+
+/// Like [`fetch`], for an object that was just listed. One that went away since is
+/// skipped, and its `*Removed` signal is already on the way.
+async fn fetch_listed(
+    connection: &Connection,
+    cache: &mut Cache,
+    object: &LogindObject,
+    path: &OwnedObjectPath,
+) -> zbus::Result<()> {
+    match fetch(connection, cache, object, path).await {
+        Err(zbus::Error::FDO(e)) if matches!(*e, fdo::Error::UnknownObject(_)) => Ok(()),
+        result => result,
+    }
+}
+
+/// Read all properties of `object` into `cache`
+async fn fetch(
+    connection: &Connection,
+    cache: &mut Cache,
+    object: &LogindObject,
+    path: &OwnedObjectPath,
+) -> zbus::Result<()> {
+    let interface = match object {
+        LogindObject::Session(_) => "org.freedesktop.login1.Session",
+        LogindObject::Seat(_) => "org.freedesktop.login1.Seat",
+        LogindObject::User(_) => "org.freedesktop.login1.User",
+        LogindObject::Manager => return Ok(()),
+    };
+    let properties = PropertiesProxy::builder(connection)
+        .destination(DESTINATION)?
+        .path(path.clone())?
+        .build()
+        .await?
+        .get_all(InterfaceName::from_static_str_unchecked(interface))
+        .await?;
+    cache.insert(object, path, properties);
+    Ok(())
+}
@@ -0,0 +1,168 @@
+use std::{collections::HashMap, sync::RwLock};
+
+use crate::events::LogindObject;
+use crate::session::{SessionState, SessionType};
+use crate::testing::{self, FakeSession};
+use crate::user::UserState;
+use futures_lite::future;
+use zbus::zvariant::{self, OwnedValue};
+
+use super::{changed_properties, fetch_listed, Cache, LogindState, Shared, StateChange};
+
+#[test]
+fn snapshot() {
+    future::block_on(async {
+        let fake = testing::desktop().await;
+        let state = LogindState::new(fake.connection()).await.unwrap();
+
+        let session = state.active_session("seat0").unwrap();
+        assert_eq!(session.id(), "2");
+        assert_eq!(session.uid(), 1000);
+        assert_eq!(session.seat(), Some("seat0"));
+        assert_eq!(*session.session_type(), SessionType::Wayland);
+        assert_eq!(session.vtnr(), 2);
+        assert_eq!(state.sessions_of(1000), vec![session]);
+        assert!(state.sessions_of(1001).is_empty());
+
+        let user = state.user(1000).unwrap();
+        assert_eq!(user.name(), "user");
+        assert_eq!(*user.state(), UserState::Active);
+        assert_eq!(state.seats().len(), 1);
+        assert!(state.active_session("seat1").is_none());
+    })
+}
+
+#[test]
+fn changes() {
+    future::block_on(async {
+        let fake = testing::desktop().await;
+        let state = LogindState::new(fake.connection()).await.unwrap();
+        let changes = state.changes();
+        let c1 = LogindObject::Session("c1".to_string());
+
+        fake.add_session(FakeSession {
+            seat: Some("seat0".to_string()),
+            ..FakeSession::new("c1", 1000)
+        })
+        .await
+        .unwrap();
+        while changes.recv().await.unwrap() != StateChange::Added(c1.clone()) {}
+        assert_eq!(state.sessions_of(1000).len(), 2);
+        assert!(!state.session("c1").unwrap().active());
+
+        fake.update_session("c1", |s| s.state = SessionState::Active)
+            .await
+            .unwrap();
+        while changes.recv().await.unwrap() != StateChange::Changed(c1.clone()) {}
+        assert!(state.session("c1").unwrap().active());
+
+        fake.remove_session("c1").await.unwrap();
+        while changes.recv().await.unwrap() != StateChange::Removed(c1.clone()) {}
+        assert!(state.session("c1").is_none());
+    })
+}
+
+#[test]
+fn changes_blocking() {
+    let fake = future::block_on(testing::desktop());
+    let connection = fake.blocking_connection();
+    let state = LogindState::new_blocking(&connection).unwrap();
+    let changes = state.changes();
+
+    future::block_on(fake.remove_session("2")).unwrap();
+    let removed = StateChange::Removed(LogindObject::Session("2".to_string()));
+    while changes.recv_blocking().unwrap() != removed {}
+    assert!(state.active_session("seat0").is_none());
+    assert!(state.sessions().is_empty());
+}
+
+#[test]
+fn uncached_objects() {
+    future::block_on(async {
+        let fake = testing::desktop().await;
+        let connection = fake.connection();
+        let gone = LogindObject::Session("c1".to_string());
+
+        // A session that ended between being listed and being read is left out
+        let mut cache = Cache::default();
+        let path = "/org/freedesktop/login1/session/c1".try_into().unwrap();
+        fetch_listed(connection, &mut cache, &gone, &path)
+            .await
+            .unwrap();
+        assert!(cache.sessions.is_empty());
+
+        // and changes to it are ignored
+        let shared = Shared::default();
+        let change = changed_properties(
+            connection,
+            &shared,
+            gone,
+            Default::default(),
+            vec!["Active".to_string()],
+        )
+        .await;
+        assert_eq!(change, None);
+    })
+}
+
+#[test]
+fn unparsed_change() {
+    future::block_on(async {
+        let fake = testing::desktop().await;
+        let connection = fake.connection();
+        let session = LogindObject::Session("2".to_string());
+        let path = "/org/freedesktop/login1/session/_32".try_into().unwrap();
+        let mut cache = Cache::default();
+        fetch_listed(connection, &mut cache, &session, &path)
+            .await
+            .unwrap();
+        cache.sessions.get_mut("2").unwrap().active = false;
+        let shared = Shared {
+            cache: RwLock::new(cache),
+            ..Default::default()
+        };
+
+        // A value of the wrong type makes the session be read again
+        let changed = HashMap::from([("Active".to_string(), OwnedValue::from(7u32))]);
+        let change =
+            changed_properties(connection, &shared, session.clone(), changed, vec![]).await;
+        assert_eq!(change, Some(StateChange::Changed(session)));
+        assert!(shared.cache.read().unwrap().sessions["2"].active);
+    })
+}
+
+#[test]
+fn unparsed_property() {
+    future::block_on(async {
+        let fake = testing::desktop().await;
+        let connection = fake.connection();
+        let session = LogindObject::Session("2".to_string());
+        let path = "/org/freedesktop/login1/session/_32".try_into().unwrap();
+
+        // A value that does not parse marks the session as needing another read
+        let mut cache = Cache::default();
+        let properties = HashMap::from([
+            (
+                "Name".to_string(),
+                OwnedValue::from(zvariant::Str::from("user")),
+            ),
+            ("Active".to_string(), OwnedValue::from(7u32)),
+        ]);
+        cache.insert(&session, &path, properties);
+        assert_eq!(cache.sessions["2"].name(), "user");
+        assert!(cache.stale.contains(&session));
+        let shared = Shared {
+            cache: RwLock::new(cache),
+            ..Default::default()
+        };
+
+        // which its next change does, even one that parses
+        let changed = HashMap::from([("IdleHint".to_string(), OwnedValue::from(false))]);
+        let change =
+            changed_properties(connection, &shared, session.clone(), changed, vec![]).await;
+        assert_eq!(change, Some(StateChange::Changed(session.clone())));
+        let cache = shared.cache.read().unwrap();
+        assert!(cache.sessions["2"].active);
+        assert!(!cache.stale.contains(&session));
+    })
+}
@@ -0,0 +1,1154 @@
+//! `org.freedesktop.login1.Manager` as served by [`super::FakeLogind`]
+
+use std::{
+    collections::HashMap,
+    os::unix::{
+        io::{FromRawFd, IntoRawFd},
+        net::UnixStream,
+    },
+    str::FromStr,
+    sync::Arc,
+};
+
+use zbus::{
+    dbus_interface, fdo,
+    zvariant::{self, ObjectPath, OwnedObjectPath, OwnedValue, Value},
+    Connection, SignalContext,
+};
+
+use super::{
+    seat_path, session_path, user_path, value, Error, FakeInhibitor, FakeManager, FakeSession,
+    Shared, State, MANAGER_PATH,
+};
+use crate::{
+    manager::{InhibitType, InhibitTypes, IsSupported, Mode, PowerFlags, ShutdownKind},
+    session::{SessionClass, SessionState, SessionType},
+};
+
+/// One entry of `ListSessionsEx`
+type SessionEx = (
+    String,
+    u32,
+    String,
+    String,
+    u32,
+    String,
+    String,
+    bool,
+    u64,
+    OwnedObjectPath,
+);
+
+pub(super) struct ManagerIface {
+    shared: Arc<Shared>,
+}
+
+impl ManagerIface {
+    pub(super) fn new(shared: Arc<Shared>) -> Self {
+        Self { shared }
+    }
+
+    fn can(
+        &self,
+        member: &str,
+        f: impl FnOnce(&State) -> IsSupported,
+    ) -> Result<IsSupported, Error> {
+        let state = self.shared.state();
+        if state.manager.missing.contains(&member) {
+            return Err(Error::unknown_method(member));
+        }
+        Ok(f(&state))
+    }
+
+    /// A property an older logind lacks, unknown while it is in `missing`
+    fn newer<T>(&self, name: &str, f: impl FnOnce(&FakeManager) -> T) -> fdo::Result<T> {
+        let state = self.shared.state();
+        if state.manager.missing.contains(&name) {
+            return Err(fdo::Error::UnknownProperty(format!(
+                "Unknown property {name}"
+            )));
+        }
+        Ok(f(&state.manager))
+    }
+
+    /// Check `flags` as logind does before recording a `*WithFlags` call
+    fn with_flags(&self, member: &str, flags: u64, reboot: bool) -> Result<(), Error> {
+        if self.shared.state().manager.missing.contains(&member) {
+            return Err(Error::unknown_method(member));
+        }
+        let flags = PowerFlags::from_bits(flags)
+            .ok_or_else(|| Error::invalid_args("Invalid flags parameter"))?;
+        let reboot_only = PowerFlags::REBOOT_VIA_KEXEC
+            | PowerFlags::SOFT_REBOOT
+            | PowerFlags::SOFT_REBOOT_IF_NEXTROOT_SET_UP;
+        if !reboot && flags.intersects(reboot_only) {
+            return Err(Error::invalid_args(
+                "Reboot via kexec or soft-reboot is only applicable with reboot operations",
+            ));
+        }
+        self.record(member, vec![value(flags.bits())]);
+        Ok(())
+    }
+
+    /// Record a call that only has an effect on a real machine
+    fn record(&self, member: &str, args: Vec<OwnedValue>) {
+        self.shared.state().record(
+            &ObjectPath::from_static_str_unchecked(MANAGER_PATH),
+            member,
+            args,
+        );
+    }
+
+    async fn update<F, R>(&self, conn: &Connection, f: F) -> Result<R, Error>
+    where
+        F: FnOnce(&mut State) -> Result<R, Error>,
+    {
+        Shared::update(&self.shared, conn, f).await?
+    }
+}
+
+#[dbus_interface(name = "org.freedesktop.login1.Manager")]
+impl ManagerIface {
+    async fn activate_session(
+        &self,
+        #[zbus(connection)] conn: &Connection,
+        session_id: &str,
+    ) -> Result<(), Error> {
+        self.update(conn, |state| activate(state, session_id, None))
+            .await
+    }
+
+    async fn activate_session_on_seat(
+        &self,
+        #[zbus(connection)] conn: &Connection,
+        session_id: &str,
+        seat_id: &str,
+    ) -> Result<(), Error> {
+        self.update(conn, |state| activate(state, session_id, Some(seat_id)))
+            .await
+    }
+
+    fn attach_device(&self, seat_id: &str, sysfs_path: &str, interactive: bool) {
+        self.record(
+            "AttachDevice",
+            vec![value(seat_id), value(sysfs_path), value(interactive)],
+        );
+    }
+
+    fn can_halt(&self) -> Result<IsSupported, Error> {
+        self.can("CanHalt", |s| s.manager.can_halt.clone())
+    }
+
+    fn can_hibernate(&self) -> Result<IsSupported, Error> {
+        self.can("CanHibernate", |s| s.manager.can_hibernate.clone())
+    }
+
+    fn can_hybrid_sleep(&self) -> Result<IsSupported, Error> {
+        self.can("CanHybridSleep", |s| s.manager.can_hybrid_sleep.clone())
+    }
+
+    fn can_power_off(&self) -> Result<IsSupported, Error> {
+        self.can("CanPowerOff", |s| s.manager.can_power_off.clone())
+    }
+
+    fn can_reboot(&self) -> Result<IsSupported, Error> {
+        self.can("CanReboot", |s| s.manager.can_reboot.clone())
+    }
+
+    fn can_reboot_parameter(&self) -> Result<IsSupported, Error> {
+        self.can("CanRebootParameter", |s| {
+            s.manager.can_reboot_parameter.clone()
+        })
+    }
+
+    fn can_reboot_to_boot_loader_entry(&self) -> Result<IsSupported, Error> {
+        self.can("CanRebootToBootLoaderEntry", |s| {
+            s.manager.can_reboot_to_boot_loader_entry.clone()
+        })
+    }
+
+    fn can_reboot_to_boot_loader_menu(&self) -> Result<IsSupported, Error> {
+        self.can("CanRebootToBootLoaderMenu", |s| {
+            s.manager.can_reboot_to_boot_loader_menu.clone()
+        })
+    }
+
+    fn can_reboot_to_firmware_setup(&self) -> Result<IsSupported, Error> {
+        self.can("CanRebootToFirmwareSetup", |s| {
+            s.manager.can_reboot_to_firmware_setup.clone()
+        })
+    }
+
+    fn can_sleep(&self) -> Result<IsSupported, Error> {
+        self.can("CanSleep", |s| s.manager.can_sleep.clone())
+    }
+
+    fn can_suspend(&self) -> Result<IsSupported, Error> {
+        self.can("CanSuspend", |s| s.manager.can_suspend.clone())
+    }
+
+    fn can_suspend_then_hibernate(&self) -> Result<IsSupported, Error> {
+        self.can("CanSuspendThenHibernate", |s| {
+            s.manager.can_suspend_then_hibernate.clone()
+        })
+    }
+
+    async fn cancel_scheduled_shutdown(
+        &self,
+        #[zbus(connection)] conn: &Connection,
+    ) -> Result<bool, Error> {
+        self.update(conn, |state| {
+            let scheduled = !state.manager.scheduled_shutdown.0.is_empty();
+            state.manager.scheduled_shutdown = (String::new(), 0);
+            Ok(scheduled)
+        })
+        .await
+    }
+
+    fn flush_devices(&self, interactive: bool) {
+        self.record("FlushDevices", vec![value(interactive)]);
+    }
+
+    fn get_seat(&self, seat_id: &str) -> Result<OwnedObjectPath, Error> {
+        self.shared.state().seat(seat_id)?;
+        Ok(seat_path(seat_id))
+    }
+
+    fn get_session(&self, session_id: &str) -> Result<OwnedObjectPath, Error> {
+        self.shared.state().session(session_id)?;
+        Ok(session_path(session_id))
+    }
+
+    #[dbus_interface(name = "GetSessionByPID")]
+    fn get_session_by_pid(&self, pid: u32) -> Result<OwnedObjectPath, Error> {
+        self.shared
+            .state()
+            .sessions
+            .values()
+            .find(|s| s.leader == pid)
+            .map(|s| session_path(&s.id))
+            .ok_or_else(|| {
+                Error::new(
+                    "org.freedesktop.login1.NoSessionForPID",
+                    format!("PID {pid} does not belong to any known session"),
+                )
+            })
+    }
+
+    fn get_user(&self, uid: u32) -> Result<OwnedObjectPath, Error> {
+        self.shared.state().user(uid)?;
+        Ok(user_path(uid))
+    }
+
+    #[dbus_interface(name = "GetUserByPID")]
+    fn get_user_by_pid(&self, pid: u32) -> Result<OwnedObjectPath, Error> {
+        self.shared
+            .state()
+            .sessions
+            .values()
+            .find(|s| s.leader == pid)
+            .map(|s| user_path(s.uid))
+            .ok_or_else(|| {
+                Error::new(
+                    "org.freedesktop.login1.NoUserForPID",
+                    format!("PID {pid} does not belong to any logged in user or lingering user"),
+                )
+            })
+    }
+
+    fn halt(&self, interactive: bool) {
+        self.record("Halt", vec![value(interactive)]);
+    }
+
+    fn halt_with_flags(&self, flags: u64) -> Result<(), Error> {
+        self.with_flags("HaltWithFlags", flags, false)
+    }
+
+    fn hibernate(&self, interactive: bool) {
+        self.record("Hibernate", vec![value(interactive)]);
+    }
+
+    fn hibernate_with_flags(&self, flags: u64) -> Result<(), Error> {
+        self.with_flags("HibernateWithFlags", flags, false)
+    }
+
+    fn hybrid_sleep(&self, interactive: bool) {
+        self.record("HybridSleep", vec![value(interactive)]);
+    }
+
+    fn hybrid_sleep_with_flags(&self, flags: u64) -> Result<(), Error> {
+        self.with_flags("HybridSleepWithFlags", flags, false)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn create_session(
+        &self,
+        #[zbus(connection)] conn: &Connection,
+        uid: u32,
+        pid: u32,
+        service: &str,
+        type_: &str,
+        class: &str,
+        desktop: &str,
+        seat_id: &str,
+        vtnr: u32,
+        tty: &str,
+        display: &str,
+        remote: bool,
+        remote_user: &str,
+        remote_host: &str,
+        properties: Vec<(String, OwnedValue)>,
+    ) -> Result<
+        (
+            String,
+            OwnedObjectPath,
+            String,
+            zvariant::OwnedFd,
+            u32,
+            String,
+            u32,
+            bool,
+        ),
+        Error,
+    > {
+        let type_ = match type_ {
+            "" => SessionType::Unspecified,
+            t => SessionType::from(t),
+        };
+        if let SessionType::Other(_) = type_ {
+            return Err(Error::invalid_args(format!("Invalid session type {type_}")));
+        }
+        let class = match class {
+            "" => SessionClass::User,
+            c => SessionClass::from(c),
+        };
+        if let SessionClass::Other(_) = class {
+            return Err(Error::invalid_args(format!(
+                "Invalid session class {class}"
+            )));
+        }
+
+        let (ours, theirs) = UnixStream::pair().map_err(|e| Error::from(zbus::Error::from(e)))?;
+        let seat = (!seat_id.is_empty()).then(|| seat_id.to_string());
+        let id = self
+            .update(conn, |state| {
+                state.next_session += 1;
+                let mut session = FakeSession::new(format!("c{}", state.next_session), uid);
+                session.leader = pid;
+                session.service = service.to_string();
+                session.type_ = type_;
+                session.class = class;
+                session.desktop = desktop.to_string();
+                session.vtnr = vtnr;
+                session.tty = tty.to_string();
+                session.display = display.to_string();
+                session.remote = remote;
+                session.remote_user = remote_user.to_string();
+                session.remote_host = remote_host.to_string();
+                if let Some(seat) = &seat {
+                    if state.active_session(seat).is_none() {
+                        session.state = SessionState::Active;
+                    }
+                }
+                session.seat = seat.clone();
+                let id = session.id.clone();
+                state.insert_session(session);
+                state.record(
+                    &ObjectPath::from_static_str_unchecked(MANAGER_PATH),
+                    "CreateSession",
+                    vec![
+                        value(uid),
+                        value(pid),
+                        value(service),
+                        value(properties.len() as u32),
+                    ],
+                );
+                Ok(id)
+            })
+            .await?;
+        let session_id = id.clone();
+        Shared::watch_fd(&self.shared, conn, ours, move |state| {
+            state.sessions.remove(&session_id);
+        });
+
+        let path = session_path(&id);
+        // SAFETY: the descriptor was just taken out of a socket we own
+        let fifo = unsafe { zvariant::OwnedFd::from_raw_fd(theirs.into_raw_fd()) };
+        Ok((
+            id,
+            path,
+            format!("/run/user/{uid}"),
+            fifo,
+            uid,
+            seat.unwrap_or_default(),
+            vtnr,
+            false,
+        ))
+    }
+
+    async fn inhibit(
+        &self,
+        #[zbus(connection)] conn: &Connection,
+        what: &str,
+        who: &str,
+        why: &str,
+        mode: &str,
+    ) -> Result<zvariant::OwnedFd, Error> {
+        let what = InhibitTypes::from_str(what).map_err(|e| Error::invalid_args(e.to_string()))?;
+        let mode = Mode::from_str(mode).map_err(|e| Error::invalid_args(e.to_string()))?;
+        if mode == Mode::Delay
+            && what
+                .iter()
+                .any(|w| !matches!(w, InhibitType::Shutdown | InhibitType::Sleep))
+        {
+            return Err(Error::invalid_args(
+                "Delay inhibitors only supported for shutdown and sleep",
+            ));
+        }
+
+        let (ours, theirs) = UnixStream::pair().map_err(|e| Error::from(zbus::Error::from(e)))?;
+        let inhibitor = FakeInhibitor {
+            what,
+            who: who.to_string(),
+            why: why.to_string(),
+            mode,
+            uid: 0,
+            pid: std::process::id(),
+        };
+        let id = self
+            .update(conn, |state| Ok(state.push_inhibitor(inhibitor)))
+            .await?;
+        Shared::watch_fd(&self.shared, conn, ours, move |state| {
+            state.inhibitors.retain(|held| held.id != id)
+        });
+
+        // SAFETY: the descriptor was just taken out of a socket we own
+        Ok(unsafe { zvariant::OwnedFd::from_raw_fd(theirs.into_raw_fd()) })
+    }
+
+    fn kill_session(&self, session_id: &str, who: &str, signal_number: i32) -> Result<(), Error> {
+        let mut state = self.shared.state();
+        state.session(session_id)?;
+        state.record(
+            &ObjectPath::from_static_str_unchecked(MANAGER_PATH),
+            "KillSession",
+            vec![value(session_id), value(who), value(signal_number)],
+        );
+        Ok(())
+    }
+
+    fn kill_user(&self, uid: u32, signal_number: i32) -> Result<(), Error> {
+        let mut state = self.shared.state();
+        state.user(uid)?;
+        state.record(
+            &ObjectPath::from_static_str_unchecked(MANAGER_PATH),
+            "KillUser",
+            vec![value(uid), value(signal_number)],
+        );
+        Ok(())
+    }
+
+    fn list_inhibitors(&self) -> Vec<(String, String, String, String, u32, u32)> {
+        self.shared
+            .state()
+            .inhibitors
+            .iter()
+            .map(|held| {
+                let i = &held.inhibitor;
+                (
+                    String::from(&i.what),
+                    i.who.clone(),
+                    i.why.clone(),
+                    i.mode.to_string(),
+                    i.uid,
+                    i.pid,
+                )
+            })
+            .collect()
+    }
+
+    fn list_seats(&self) -> Vec<(String, OwnedObjectPath)> {
+        self.shared
+            .state()
+            .seats
+            .keys()
+            .map(|id| (id.clone(), seat_path(id)))
+            .collect()
+    }
+
+    fn list_sessions(&self) -> Vec<(String, u32, String, String, OwnedObjectPath)> {
+        let state = self.shared.state();
+        state
+            .sessions
+            .values()
+            .map(|s| {
+                let user = state
+                    .users
+                    .get(&s.uid)
+                    .map(|u| u.name.clone())
+                    .unwrap_or_default();
+                (
+                    s.id.clone(),
+                    s.uid,
+                    user,
+                    s.seat.clone().unwrap_or_default(),
+                    session_path(&s.id),
+                )
+            })
+            .collect()
+    }
+
+    fn list_sessions_ex(&self) -> Result<Vec<SessionEx>, Error> {
+        let state = self.shared.state();
+        if state.manager.missing.contains(&"ListSessionsEx") {
+            return Err(Error::unknown_method("ListSessionsEx"));
+        }
+        Ok(state
+            .sessions
+            .values()
+            .map(|s| {
+                let user = state
+                    .users
+                    .get(&s.uid)
+                    .map(|u| u.name.clone())
+                    .unwrap_or_default();
+                (
+                    s.id.clone(),
+                    s.uid,
+                    user,
+                    s.seat.clone().unwrap_or_default(),
+                    s.leader,
+                    s.class.to_string(),
+                    s.tty.clone(),
+                    s.idle_hint,
+                    s.idle_since_hint_monotonic,
+                    session_path(&s.id),
+                )
+            })
+            .collect())
+    }
+
+    fn list_users(&self) -> Vec<(u32, String, OwnedObjectPath)> {
+        self.shared
+            .state()
+            .users
+            .values()
+            .map(|u| (u.uid, u.name.clone(), user_path(u.uid)))
+            .collect()
+    }
+
+    async fn lock_session(
+        &self,
+        #[zbus(connection)] conn: &Connection,
+        session_id: &str,
+    ) -> Result<(), Error> {
+        self.shared.state().session(session_id)?;
+        let ctxt = SignalContext::new(conn, session_path(session_id))?;
+        super::SessionIface::lock(&ctxt).await?;
+        Ok(())
+    }
+
+    async fn lock_sessions(&self, #[zbus(connection)] conn: &Connection) -> Result<(), Error> {
+        let ids: Vec<String> = self.shared.state().sessions.keys().cloned().collect();
+        for id in ids {
+            let ctxt = SignalContext::new(conn, session_path(&id))?;
+            super::SessionIface::lock(&ctxt).await?;
+        }
+        Ok(())
+    }
+
+    fn power_off(&self, interactive: bool) {
+        self.record("PowerOff", vec![value(interactive)]);
+    }
+
+    fn power_off_with_flags(&self, flags: u64) -> Result<(), Error> {
+        self.with_flags("PowerOffWithFlags", flags, false)
+    }
+
+    fn reboot(&self, interactive: bool) {
+        self.record("Reboot", vec![value(interactive)]);
+    }
+
+    fn reboot_with_flags(&self, flags: u64) -> Result<(), Error> {
+        self.with_flags("RebootWithFlags", flags, true)
+    }
+
+    async fn release_session(
+        &self,
+        #[zbus(connection)] conn: &Connection,
+        session_id: &str,
+    ) -> Result<(), Error> {
+        self.update(conn, |state| {
+            state.session(session_id)?;
+            state.sessions.remove(session_id);
+            Ok(())
+        })
+        .await
+    }
+
+    async fn schedule_shutdown(
+        &self,
+        #[zbus(connection)] conn: &Connection,
+        type_: &str,
+        usec: u64,
+    ) -> Result<(), Error> {
+        if let ShutdownKind::Other(_) = ShutdownKind::from(type_) {
+            return Err(Error::invalid_args(format!(
+                "Unsupported shutdown type: {type_}"
+            )));
+        }
+        self.update(conn, |state| {
+            state.manager.scheduled_shutdown = (type_.to_string(), usec);
+            Ok(())
+        })
+        .await
+    }
+
+    async fn set_reboot_parameter(
+        &self,
+        #[zbus(connection)] conn: &Connection,
+        parameter: &str,
+    ) -> Result<(), Error> {
+        self.update(conn, |state| {
+            state.manager.reboot_parameter = parameter.to_string();
+            Ok(())
+        })
+        .await
+    }
+
+    async fn set_reboot_to_boot_loader_entry(
+        &self,
+        #[zbus(connection)] conn: &Connection,
+        boot_loader_entry: &str,
+    ) -> Result<(), Error> {
+        self.update(conn, |state| {
+            state.manager.reboot_to_boot_loader_entry = boot_loader_entry.to_string();
+            Ok(())
+        })
+        .await
+    }
+
+    async fn set_reboot_to_boot_loader_menu(
+        &self,
+        #[zbus(connection)] conn: &Connection,
+        timeout: u64,
+    ) -> Result<(), Error> {
+        self.update(conn, |state| {
+            state.manager.reboot_to_boot_loader_menu = timeout;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn set_reboot_to_firmware_setup(
+        &self,
+        #[zbus(connection)] conn: &Connection,
+        enable: bool,
+    ) -> Result<(), Error> {
+        self.update(conn, |state| {
+            state.manager.reboot_to_firmware_setup = enable;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn set_user_linger(
+        &self,
+        #[zbus(connection)] conn: &Connection,
+        uid: u32,
+        enable: bool,
+        interactive: bool,
+    ) -> Result<(), Error> {
+        self.update(conn, |state| {
+            state.record(
+                &ObjectPath::from_static_str_unchecked(MANAGER_PATH),
+                "SetUserLinger",
+                vec![value(uid), value(enable), value(interactive)],
+            );
+            if let Some(user) = state.users.get_mut(&uid) {
+                user.linger = enable;
+            } else if enable {
+                let mut user = super::FakeUser::new(uid, format!("user{uid}"));
+                user.linger = true;
+                state.users.insert(uid, user);
+            }
+            Ok(())
+        })
+        .await
+    }
+
+    async fn set_wall_message(
+        &self,
+        #[zbus(connection)] conn: &Connection,
+        wall_message: &str,
+        enable: bool,
+    ) -> Result<(), Error> {
+        self.update(conn, |state| {
+            state.manager.wall_message = wall_message.to_string();
+            state.manager.enable_wall_messages = enable;
+            Ok(())
+        })
+        .await
+    }
+
+    fn sleep(&self, flags: u64) -> Result<(), Error> {
+        self.with_flags("Sleep", flags, false)
+    }
+
+    fn suspend(&self, interactive: bool) {
+        self.record("Suspend", vec![value(interactive)]);
+    }
+
+    fn suspend_with_flags(&self, flags: u64) -> Result<(), Error> {
+        self.with_flags("SuspendWithFlags", flags, false)
+    }
+
+    fn suspend_then_hibernate(&self, interactive: bool) {
+        self.record("SuspendThenHibernate", vec![value(interactive)]);
+    }
+
+    fn suspend_then_hibernate_with_flags(&self, flags: u64) -> Result<(), Error> {
+        self.with_flags("SuspendThenHibernateWithFlags", flags, false)
+    }
+
+    async fn terminate_seat(
+        &self,
+        #[zbus(connection)] conn: &Connection,
+        seat_id: &str,
+    ) -> Result<(), Error> {
+        self.update(conn, |state| {
+            state.seat(seat_id)?;
+            state
+                .sessions
+                .retain(|_, s| s.seat.as_deref() != Some(seat_id));
+            Ok(())
+        })
+        .await
+    }
+
+    async fn terminate_session(
+        &self,
+        #[zbus(connection)] conn: &Connection,
+        session_id: &str,
+    ) -> Result<(), Error> {
+        self.update(conn, |state| {
+            state.session(session_id)?;
+            state.sessions.remove(session_id);
+            Ok(())
+        })
+        .await
+    }
+
+    async fn terminate_user(
+        &self,
+        #[zbus(connection)] conn: &Connection,
+        uid: u32,
+    ) -> Result<(), Error> {
+        self.update(conn, |state| {
+            state.user(uid)?;
+            state.sessions.retain(|_, s| s.uid != uid);
+            if !state.users[&uid].linger {
+                state.users.remove(&uid);
+            }
+            Ok(())
+        })
+        .await
+    }
+
+    async fn unlock_session(
+        &self,
+        #[zbus(connection)] conn: &Connection,
+        session_id: &str,
+    ) -> Result<(), Error> {
+        self.shared.state().session(session_id)?;
+        let ctxt = SignalContext::new(conn, session_path(session_id))?;
+        super::SessionIface::unlock(&ctxt).await?;
+        Ok(())
+    }
+
+    async fn unlock_sessions(&self, #[zbus(connection)] conn: &Connection) -> Result<(), Error> {
+        let ids: Vec<String> = self.shared.state().sessions.keys().cloned().collect();
+        for id in ids {
+            let ctxt = SignalContext::new(conn, session_path(&id))?;
+            super::SessionIface::unlock(&ctxt).await?;
+        }
+        Ok(())
+    }
+
+    #[dbus_interface(signal)]
+    pub(super) async fn prepare_for_shutdown(
+        ctxt: &SignalContext<'_>,
+        start: bool,
+    ) -> zbus::Result<()>;
+
+    #[dbus_interface(signal)]
+    pub(super) async fn prepare_for_shutdown_with_metadata(
+        ctxt: &SignalContext<'_>,
+        start: bool,
+        metadata: HashMap<&str, Value<'_>>,
+    ) -> zbus::Result<()>;
+
+    #[dbus_interface(signal)]
+    pub(super) async fn prepare_for_sleep(
+        ctxt: &SignalContext<'_>,
+        start: bool,
+    ) -> zbus::Result<()>;
+
+    #[dbus_interface(signal)]
+    pub(super) async fn secure_attention_key(
+        ctxt: &SignalContext<'_>,
+        seat_id: &str,
+        object_path: ObjectPath<'_>,
+    ) -> zbus::Result<()>;
+
+    #[dbus_interface(signal)]
+    pub(super) async fn seat_new(
+        ctxt: &SignalContext<'_>,
+        seat_id: &str,
+        object_path: ObjectPath<'_>,
+    ) -> zbus::Result<()>;
+
+    #[dbus_interface(signal)]
+    pub(super) async fn seat_removed(
+        ctxt: &SignalContext<'_>,
+        seat_id: &str,
+        object_path: ObjectPath<'_>,
+    ) -> zbus::Result<()>;
+
+    #[dbus_interface(signal)]
+    pub(super) async fn session_new(
+        ctxt: &SignalContext<'_>,
+        session_id: &str,
+        object_path: ObjectPath<'_>,
+    ) -> zbus::Result<()>;
+
+    #[dbus_interface(signal)]
+    pub(super) async fn session_removed(
+        ctxt: &SignalContext<'_>,
+        session_id: &str,
+        object_path: ObjectPath<'_>,
+    ) -> zbus::Result<()>;
+
+    #[dbus_interface(signal)]
+    pub(super) async fn user_new(
+        ctxt: &SignalContext<'_>,
+        uid: u32,
+        object_path: ObjectPath<'_>,
+    ) -> zbus::Result<()>;
+
+    #[dbus_interface(signal)]
+    pub(super) async fn user_removed(
+        ctxt: &SignalContext<'_>,
+        uid: u32,
+        object_path: ObjectPath<'_>,
+    ) -> zbus::Result<()>;
+
+    #[dbus_interface(property)]
+    fn block_inhibited(&self) -> String {
+        String::from(&self.shared.state().inhibited(Mode::Block))
+    }
+
+    #[dbus_interface(property)]
+    fn boot_loader_entries(&self) -> Vec<String> {
+        self.shared.state().manager.boot_loader_entries.clone()
+    }
+
+    #[dbus_interface(property)]
+    fn delay_inhibited(&self) -> String {
+        String::from(&self.shared.state().inhibited(Mode::Delay))
+    }
+
+    #[dbus_interface(property)]
+    fn designated_maintenance_time(&self) -> fdo::Result<String> {
+        self.newer("DesignatedMaintenanceTime", |m| {
+            m.designated_maintenance_time.clone()
+        })
+    }
+
+    #[dbus_interface(property)]
+    fn docked(&self) -> bool {
+        self.shared.state().manager.docked
+    }
+
+    #[dbus_interface(property)]
+    fn enable_wall_messages(&self) -> bool {
+        self.shared.state().manager.enable_wall_messages
+    }
+
+    #[dbus_interface(property)]
+    fn set_enable_wall_messages(&mut self, value: bool) {
+        self.shared.state().manager.enable_wall_messages = value;
+    }
+
+    #[dbus_interface(property)]
+    fn handle_hibernate_key(&self) -> String {
+        self.shared.state().manager.handle_hibernate_key.to_string()
+    }
+
+    #[dbus_interface(property)]
+    fn handle_hibernate_key_long_press(&self) -> fdo::Result<String> {
+        self.newer("HandleHibernateKeyLongPress", |m| {
+            m.handle_hibernate_key_long_press.to_string()
+        })
+    }
+
+    #[dbus_interface(property)]
+    fn handle_lid_switch(&self) -> String {
+        self.shared.state().manager.handle_lid_switch.to_string()
+    }
+
+    #[dbus_interface(property)]
+    fn handle_lid_switch_docked(&self) -> String {
+        self.shared
+            .state()
+            .manager
+            .handle_lid_switch_docked
+            .to_string()
+    }
+
+    #[dbus_interface(property)]
+    fn handle_lid_switch_external_power(&self) -> String {
+        self.shared
+            .state()
+            .manager
+            .handle_lid_switch_external_power
+            .to_string()
+    }
+
+    #[dbus_interface(property)]
+    fn handle_power_key(&self) -> String {
+        self.shared.state().manager.handle_power_key.to_string()
+    }
+
+    #[dbus_interface(property)]
+    fn handle_power_key_long_press(&self) -> fdo::Result<String> {
+        self.newer("HandlePowerKeyLongPress", |m| {
+            m.handle_power_key_long_press.to_string()
+        })
+    }
+
+    #[dbus_interface(property)]
+    fn handle_reboot_key(&self) -> fdo::Result<String> {
+        self.newer("HandleRebootKey", |m| m.handle_reboot_key.to_string())
+    }
+
+    #[dbus_interface(property)]
+    fn handle_reboot_key_long_press(&self) -> fdo::Result<String> {
+        self.newer("HandleRebootKeyLongPress", |m| {
+            m.handle_reboot_key_long_press.to_string()
+        })
+    }
+
+    #[dbus_interface(property)]
+    fn handle_secure_attention_key(&self) -> fdo::Result<String> {
+        self.newer("HandleSecureAttentionKey", |m| {
+            m.handle_secure_attention_key.to_string()
+        })
+    }
+
+    #[dbus_interface(property)]
+    fn handle_suspend_key(&self) -> String {
+        self.shared.state().manager.handle_suspend_key.to_string()
+    }
+
+    #[dbus_interface(property)]
+    fn handle_suspend_key_long_press(&self) -> fdo::Result<String> {
+        self.newer("HandleSuspendKeyLongPress", |m| {
+            m.handle_suspend_key_long_press.to_string()
+        })
+    }
+
+    #[dbus_interface(property, name = "HoldoffTimeoutUSec")]
+    fn holdoff_timeout_usec(&self) -> u64 {
+        self.shared.state().manager.holdoff_timeout_usec
+    }
+
+    #[dbus_interface(property)]
+    fn idle_action(&self) -> String {
+        self.shared.state().manager.idle_action.to_string()
+    }
+
+    #[dbus_interface(property, name = "IdleActionUSec")]
+    fn idle_action_usec(&self) -> u64 {
+        self.shared.state().manager.idle_action_usec
+    }
+
+    #[dbus_interface(property)]
+    fn idle_hint(&self) -> bool {
+        self.shared.state().manager.idle_hint
+    }
+
+    #[dbus_interface(property)]
+    fn idle_since_hint(&self) -> u64 {
+        self.shared.state().manager.idle_since_hint
+    }
+
+    #[dbus_interface(property)]
+    fn idle_since_hint_monotonic(&self) -> u64 {
+        self.shared.state().manager.idle_since_hint_monotonic
+    }
+
+    #[dbus_interface(property, name = "InhibitDelayMaxUSec")]
+    fn inhibit_delay_max_usec(&self) -> u64 {
+        self.shared.state().manager.inhibit_delay_max_usec
+    }
+
+    #[dbus_interface(property)]
+    fn inhibitors_max(&self) -> u64 {
+        self.shared.state().manager.inhibitors_max
+    }
+
+    #[dbus_interface(property)]
+    fn kill_exclude_users(&self) -> Vec<String> {
+        self.shared.state().manager.kill_exclude_users.clone()
+    }
+
+    #[dbus_interface(property)]
+    fn kill_only_users(&self) -> Vec<String> {
+        self.shared.state().manager.kill_only_users.clone()
+    }
+
+    #[dbus_interface(property)]
+    fn kill_user_processes(&self) -> bool {
+        self.shared.state().manager.kill_user_processes
+    }
+
+    #[dbus_interface(property)]
+    fn lid_closed(&self) -> bool {
+        self.shared.state().manager.lid_closed
+    }
+
+    #[dbus_interface(property, name = "NAutoVTs")]
+    fn n_auto_vts(&self) -> u32 {
+        self.shared.state().manager.n_auto_vts
+    }
+
+    #[dbus_interface(property, name = "NCurrentInhibitors")]
+    fn n_current_inhibitors(&self) -> u64 {
+        self.shared.state().inhibitors.len() as u64
+    }
+
+    #[dbus_interface(property, name = "NCurrentSessions")]
+    fn n_current_sessions(&self) -> u64 {
+        self.shared.state().sessions.len() as u64
+    }
+
+    #[dbus_interface(property)]
+    fn on_external_power(&self) -> bool {
+        self.shared.state().manager.on_external_power
+    }
+
+    #[dbus_interface(property)]
+    fn preparing_for_shutdown(&self) -> bool {
+        self.shared.state().manager.preparing_for_shutdown
+    }
+
+    #[dbus_interface(property)]
+    fn preparing_for_sleep(&self) -> bool {
+        self.shared.state().manager.preparing_for_sleep
+    }
+
+    #[dbus_interface(property)]
+    fn reboot_parameter(&self) -> String {
+        self.shared.state().manager.reboot_parameter.clone()
+    }
+
+    #[dbus_interface(property)]
+    fn reboot_to_boot_loader_entry(&self) -> String {
+        self.shared
+            .state()
+            .manager
+            .reboot_to_boot_loader_entry
+            .clone()
+    }
+
+    #[dbus_interface(property)]
+    fn reboot_to_boot_loader_menu(&self) -> u64 {
+        self.shared.state().manager.reboot_to_boot_loader_menu
+    }
+
+    #[dbus_interface(property)]
+    fn reboot_to_firmware_setup(&self) -> bool {
+        self.shared.state().manager.reboot_to_firmware_setup
+    }
+
+    #[dbus_interface(property, name = "RemoveIPC")]
+    fn remove_ipc(&self) -> bool {
+        self.shared.state().manager.remove_ipc
+    }
+
+    #[dbus_interface(property)]
+    fn runtime_directory_inodes_max(&self) -> u64 {
+        self.shared.state().manager.runtime_directory_inodes_max
+    }
+
+    #[dbus_interface(property)]
+    fn runtime_directory_size(&self) -> u64 {
+        self.shared.state().manager.runtime_directory_size
+    }
+
+    #[dbus_interface(property)]
+    fn scheduled_shutdown(&self) -> (String, u64) {
+        self.shared.state().manager.scheduled_shutdown.clone()
+    }
+
+    #[dbus_interface(property)]
+    fn sessions_max(&self) -> u64 {
+        self.shared.state().manager.sessions_max
+    }
+
+    #[dbus_interface(property)]
+    fn sleep_operation(&self) -> Vec<String> {
+        let state = self.shared.state();
+        let modes = state.manager.sleep_operation.iter();
+        modes.map(|mode| mode.to_string()).collect()
+    }
+
+    #[dbus_interface(property, name = "StopIdleSessionUSec")]
+    fn stop_idle_session_usec(&self) -> fdo::Result<u64> {
+        self.newer("StopIdleSessionUSec", |m| m.stop_idle_session_usec)
+    }
+
+    #[dbus_interface(property, name = "UserStopDelayUSec")]
+    fn user_stop_delay_usec(&self) -> u64 {
+        self.shared.state().manager.user_stop_delay_usec
+    }
+
+    #[dbus_interface(property)]
+    fn wall_message(&self) -> String {
+        self.shared.state().manager.wall_message.clone()
+    }
+}
+
+/// Make `session_id` the active session of its seat, deactivating whatever was active there
+pub(super) fn activate(
+    state: &mut State,
+    session_id: &str,
+    seat_id: Option<&str>,
+) -> Result<(), Error> {
+    let seat = state.session(session_id)?.seat.clone();
+    let seat = match (seat, seat_id) {
+        (Some(seat), Some(wanted)) if seat != wanted => {
+            return Err(Error::new(
+                "org.freedesktop.login1.SessionNotOnSeat",
+                format!("Session {session_id} not on seat {wanted}"),
+            ))
+        }
+        (Some(seat), _) => seat,
+        (None, _) => {
+            return Err(Error::new(
+                "org.freedesktop.DBus.Error.NotSupported",
+                "Session has no seat.",
+            ))
+        }
+    };
+    for session in state.sessions.values_mut() {
+        if session.seat.as_deref() == Some(seat.as_str()) && session.state == SessionState::Active {
+            session.state = SessionState::Online;
+        }
+    }
+    if let Some(session) = state.sessions.get_mut(session_id) {
+        session.state = SessionState::Active;
+    }
+    Ok(())
+}
@@ -0,0 +1,1023 @@
+//! An in-process stand-in for `systemd-logind`.
+//!
+//! [`FakeLogind`] serves `org.freedesktop.login1.Manager`, `.Session`, `.Seat` and `.User` on a
+//! private peer-to-peer connection. Hand the client side from [`FakeLogind::connection`] to any of
+//! the proxies in this crate to exercise them without a system bus or a running logind.
+//!
+//! Sessions, seats, users and inhibitors are plain structs with public fields, so tests can
+//! describe the machine they want and change it while proxies are watching. Every change emits
+//! the same signals logind would: `SessionNew`/`SessionRemoved` and friends, plus
+//! `PropertiesChanged` for any property whose value changed and that logind announces changes
+//! of. Like logind, the fake sends nothing when for example `NCurrentInhibitors` changes.
+
+mod manager;
+mod seat;
+mod session;
+#[cfg(test)]
+mod tests;
+mod user;
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fs::File,
+    io::Read,
+    os::unix::io::AsRawFd,
+    os::unix::net::UnixStream,
+    sync::{Arc, Mutex, MutexGuard},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use zbus::{
+    fdo,
+    names::ErrorName,
+    zvariant::{self, ObjectPath, OwnedObjectPath, OwnedValue, Value},
+    Connection, ConnectionBuilder, Guid, Interface, MessageHeader, SignalContext,
+};
+
+use crate::{
+    manager::{HandleAction, InhibitTypes, IsSupported, Mode, ShutdownKind, SleepMode},
+    session::{SessionClass, SessionState, SessionType},
+    user::UserState,
+};
+
+use manager::ManagerIface;
+use seat::SeatIface;
+use session::SessionIface;
+use user::UserIface;
+
+const MANAGER_PATH: &str = "/org/freedesktop/login1";
+
+/// A scripted logind serving a private peer-to-peer connection
+pub struct FakeLogind {
+    shared: Arc<Shared>,
+    server: Connection,
+    client: Connection,
+}
+
+impl FakeLogind {
+    /// Start a fake with no seats, sessions, users or inhibitors
+    pub async fn new() -> zbus::Result<Self> {
+        let shared = Arc::new(Shared::default());
+        let (server_socket, client_socket) = UnixStream::pair()?;
+        let guid = Guid::generate();
+
+        let server = ConnectionBuilder::unix_stream(server_socket)
+            .server(&guid)
+            .p2p()
+            .serve_at(MANAGER_PATH, ManagerIface::new(shared.clone()))?
+            .build();
+        let client = ConnectionBuilder::unix_stream(client_socket).p2p().build();
+        let (server, client) = futures_util::future::try_join(server, client).await?;
+
+        Ok(Self {
+            shared,
+            server,
+            client,
+        })
+    }
+
+    /// The client side of the connection, for building proxies
+    pub fn connection(&self) -> &Connection {
+        &self.client
+    }
+
+    /// The client side of the connection wrapped for the blocking proxies
+    pub fn blocking_connection(&self) -> zbus::blocking::Connection {
+        self.client.clone().into()
+    }
+
+    /// Add a seat, emitting `SeatNew`
+    pub async fn add_seat(&self, seat: FakeSeat) -> zbus::Result<()> {
+        self.update(|state| {
+            state.seats.insert(seat.id.clone(), seat);
+        })
+        .await
+    }
+
+    /// Remove a seat and every session on it
+    pub async fn remove_seat(&self, seat_id: &str) -> zbus::Result<()> {
+        self.update(|state| {
+            state.seats.remove(seat_id);
+            state
+                .sessions
+                .retain(|_, s| s.seat.as_deref() != Some(seat_id));
+        })
+        .await
+    }
+
+    /// Add a user, emitting `UserNew`
+    pub async fn add_user(&self, user: FakeUser) -> zbus::Result<()> {
+        self.update(|state| {
+            state.users.insert(user.uid, user);
+        })
+        .await
+    }
+
+    /// Remove a user and all of their sessions
+    pub async fn remove_user(&self, uid: u32) -> zbus::Result<()> {
+        self.update(|state| {
+            state.users.remove(&uid);
+            state.sessions.retain(|_, s| s.uid != uid);
+        })
+        .await
+    }
+
+    /// Add a session, emitting `SessionNew`.
+    ///
+    /// Like logind, the session's user and seat are created on the fly when they don't exist
+    /// yet.
+    pub async fn add_session(&self, session: FakeSession) -> zbus::Result<()> {
+        self.update(|state| state.insert_session(session)).await
+    }
+
+    /// Remove a session, emitting `SessionRemoved`
+    pub async fn remove_session(&self, session_id: &str) -> zbus::Result<()> {
+        self.update(|state| {
+            state.sessions.remove(session_id);
+        })
+        .await
+    }
+
+    /// Add an inhibitor that isn't backed by a file descriptor. It stays until removed with
+    /// [`FakeLogind::clear_inhibitors`].
+    pub async fn add_inhibitor(&self, inhibitor: FakeInhibitor) -> zbus::Result<()> {
+        self.update(|state| {
+            state.push_inhibitor(inhibitor);
+        })
+        .await
+    }
+
+    /// Drop every inhibitor, including those taken through `Inhibit`
+    pub async fn clear_inhibitors(&self) -> zbus::Result<()> {
+        self.update(|state| state.inhibitors.clear()).await
+    }
+
+    /// Change a session in place, emitting `PropertiesChanged` for whatever changed on it, its
+    /// seat, its user and the manager
+    pub async fn update_session<F>(&self, session_id: &str, f: F) -> zbus::Result<()>
+    where
+        F: FnOnce(&mut FakeSession),
+    {
+        self.update(|state| {
+            if let Some(session) = state.sessions.get_mut(session_id) {
+                f(session)
+            }
+        })
+        .await
+    }
+
+    /// Change a seat in place
+    pub async fn update_seat<F>(&self, seat_id: &str, f: F) -> zbus::Result<()>
+    where
+        F: FnOnce(&mut FakeSeat),
+    {
+        self.update(|state| {
+            if let Some(seat) = state.seats.get_mut(seat_id) {
+                f(seat)
+            }
+        })
+        .await
+    }
+
+    /// Change a user in place
+    pub async fn update_user<F>(&self, uid: u32, f: F) -> zbus::Result<()>
+    where
+        F: FnOnce(&mut FakeUser),
+    {
+        self.update(|state| {
+            if let Some(user) = state.users.get_mut(&uid) {
+                f(user)
+            }
+        })
+        .await
+    }
+
+    /// Change the manager properties and `Can*` answers
+    pub async fn update_manager<F>(&self, f: F) -> zbus::Result<()>
+    where
+        F: FnOnce(&mut FakeManager),
+    {
+        self.update(|state| f(&mut state.manager)).await
+    }
+
+    /// Set `PreparingForSleep` and emit `PrepareForSleep`
+    pub async fn prepare_for_sleep(&self, start: bool) -> zbus::Result<()> {
+        self.update(|state| state.manager.preparing_for_sleep = start)
+            .await?;
+        let ctxt = SignalContext::new(&self.server, MANAGER_PATH)?;
+        ManagerIface::prepare_for_sleep(&ctxt, start).await
+    }
+
+    /// Set `PreparingForShutdown` and emit `PrepareForShutdown`, then
+    /// `PrepareForShutdownWithMetadata` for a power off
+    pub async fn prepare_for_shutdown(&self, start: bool) -> zbus::Result<()> {
+        self.prepare_for_shutdown_as(start, ShutdownKind::PowerOff)
+            .await
+    }
+
+    /// Like [`Self::prepare_for_shutdown`], with `kind` in the metadata
+    pub async fn prepare_for_shutdown_as(
+        &self,
+        start: bool,
+        kind: ShutdownKind,
+    ) -> zbus::Result<()> {
+        self.update(|state| state.manager.preparing_for_shutdown = start)
+            .await?;
+        let ctxt = SignalContext::new(&self.server, MANAGER_PATH)?;
+        ManagerIface::prepare_for_shutdown(&ctxt, start).await?;
+        let metadata = HashMap::from([("type", Value::from(kind.as_str()))]);
+        ManagerIface::prepare_for_shutdown_with_metadata(&ctxt, start, metadata).await
+    }
+
+    /// Emit `SecureAttentionKey` for `seat_id`
+    pub async fn secure_attention_key(&self, seat_id: &str) -> zbus::Result<()> {
+        let ctxt = SignalContext::new(&self.server, MANAGER_PATH)?;
+        ManagerIface::secure_attention_key(&ctxt, seat_id, seat_path(seat_id).into_inner()).await
+    }
+
+    /// Emit `PauseDevice` on a session. `kind` is sent as given; `"gone"` also forgets the device.
+    pub async fn pause_device(
+        &self,
+        session_id: &str,
+        major: u32,
+        minor: u32,
+        kind: &str,
+    ) -> zbus::Result<()> {
+        if kind == "gone" {
+            self.shared
+                .state()
+                .devices
+                .remove(&(session_id.to_string(), major, minor));
+        }
+        let ctxt = SignalContext::new(&self.server, session_path(session_id))?;
+        SessionIface::pause_device(&ctxt, major, minor, kind).await
+    }
+
+    /// Emit `ResumeDevice` on a session, with a fresh fd for `/dev/null`
+    pub async fn resume_device(
+        &self,
+        session_id: &str,
+        major: u32,
+        minor: u32,
+    ) -> zbus::Result<()> {
+        let file = File::open("/dev/null")?;
+        let ctxt = SignalContext::new(&self.server, session_path(session_id))?;
+        SessionIface::resume_device(&ctxt, major, minor, zvariant::Fd::from(file.as_raw_fd())).await
+    }
+
+    /// Devices currently taken with `TakeDevice`, as `(session id, major, minor)`
+    pub fn taken_devices(&self) -> Vec<(String, u32, u32)> {
+        self.shared.state().devices.iter().cloned().collect()
+    }
+
+    /// Every mutating method called by clients so far, oldest first
+    pub fn calls(&self) -> Vec<FakeCall> {
+        self.shared.state().calls.clone()
+    }
+
+    /// The inhibitors currently held, including those taken through `Inhibit`
+    pub fn inhibitors(&self) -> Vec<FakeInhibitor> {
+        self.shared
+            .state()
+            .inhibitors
+            .iter()
+            .map(|held| held.inhibitor.clone())
+            .collect()
+    }
+
+    async fn update<F>(&self, f: F) -> zbus::Result<()>
+    where
+        F: FnOnce(&mut State),
+    {
+        Shared::update(&self.shared, &self.server, f).await
+    }
+}
+
+/// Manager-wide properties and the answers given to the `Can*` methods
+#[derive(Debug, Clone, PartialEq)]
+pub struct FakeManager {
+    pub can_halt: IsSupported,
+    pub can_hibernate: IsSupported,
+    pub can_hybrid_sleep: IsSupported,
+    pub can_power_off: IsSupported,
+    pub can_reboot: IsSupported,
+    pub can_reboot_parameter: IsSupported,
+    pub can_reboot_to_boot_loader_entry: IsSupported,
+    pub can_reboot_to_boot_loader_menu: IsSupported,
+    pub can_reboot_to_firmware_setup: IsSupported,
+    pub can_sleep: IsSupported,
+    pub can_suspend: IsSupported,
+    pub can_suspend_then_hibernate: IsSupported,
+    pub boot_loader_entries: Vec<String>,
+    /// A calendar event, empty when none is set
+    pub designated_maintenance_time: String,
+    pub docked: bool,
+    pub enable_wall_messages: bool,
+    pub handle_hibernate_key: HandleAction,
+    pub handle_hibernate_key_long_press: HandleAction,
+    pub handle_lid_switch: HandleAction,
+    pub handle_lid_switch_docked: HandleAction,
+    pub handle_lid_switch_external_power: HandleAction,
+    pub handle_power_key: HandleAction,
+    pub handle_power_key_long_press: HandleAction,
+    pub handle_reboot_key: HandleAction,
+    pub handle_reboot_key_long_press: HandleAction,
+    pub handle_secure_attention_key: HandleAction,
+    pub handle_suspend_key: HandleAction,
+    pub handle_suspend_key_long_press: HandleAction,
+    pub holdoff_timeout_usec: u64,
+    pub idle_action: HandleAction,
+    pub idle_action_usec: u64,
+    pub idle_hint: bool,
+    pub idle_since_hint: u64,
+    pub idle_since_hint_monotonic: u64,
+    pub inhibit_delay_max_usec: u64,
+    pub inhibitors_max: u64,
+    pub kill_exclude_users: Vec<String>,
+    pub kill_only_users: Vec<String>,
+    pub kill_user_processes: bool,
+    pub lid_closed: bool,
+    pub n_auto_vts: u32,
+    pub on_external_power: bool,
+    pub preparing_for_shutdown: bool,
+    pub preparing_for_sleep: bool,
+    pub reboot_parameter: String,
+    pub reboot_to_boot_loader_entry: String,
+    pub reboot_to_boot_loader_menu: u64,
+    pub reboot_to_firmware_setup: bool,
+    pub remove_ipc: bool,
+    pub runtime_directory_inodes_max: u64,
+    pub runtime_directory_size: u64,
+    /// The kind and realtime in microseconds of a scheduled shutdown, empty and zero when none
+    pub scheduled_shutdown: (String, u64),
+    pub sessions_max: u64,
+    pub sleep_operation: Vec<SleepMode>,
+    pub stop_idle_session_usec: u64,
+    pub user_stop_delay_usec: u64,
+    pub wall_message: String,
+    /// Members to answer with `UnknownMethod` or `UnknownProperty`, as a logind too old to
+    /// have them would. Only the `Can*`, `*WithFlags` and `ListSessionsEx` methods and the
+    /// properties added since systemd 250 check this.
+    pub missing: Vec<&'static str>,
+}
+
+impl Default for FakeManager {
+    fn default() -> Self {
+        Self {
+            can_halt: IsSupported::Yes,
+            can_hibernate: IsSupported::NA,
+            can_hybrid_sleep: IsSupported::NA,
+            can_power_off: IsSupported::Yes,
+            can_reboot: IsSupported::Yes,
+            can_reboot_parameter: IsSupported::Yes,
+            can_reboot_to_boot_loader_entry: IsSupported::NA,
+            can_reboot_to_boot_loader_menu: IsSupported::NA,
+            can_reboot_to_firmware_setup: IsSupported::NA,
+            can_sleep: IsSupported::Yes,
+            can_suspend: IsSupported::Yes,
+            can_suspend_then_hibernate: IsSupported::NA,
+            boot_loader_entries: Vec::new(),
+            designated_maintenance_time: String::new(),
+            docked: false,
+            enable_wall_messages: false,
+            handle_hibernate_key: HandleAction::Hibernate,
+            handle_hibernate_key_long_press: HandleAction::Ignore,
+            handle_lid_switch: HandleAction::Suspend,
+            handle_lid_switch_docked: HandleAction::Ignore,
+            handle_lid_switch_external_power: HandleAction::Suspend,
+            handle_power_key: HandleAction::PowerOff,
+            handle_power_key_long_press: HandleAction::Ignore,
+            handle_reboot_key: HandleAction::Reboot,
+            handle_reboot_key_long_press: HandleAction::PowerOff,
+            handle_secure_attention_key: HandleAction::SecureAttentionKey,
+            handle_suspend_key: HandleAction::Suspend,
+            handle_suspend_key_long_press: HandleAction::Hibernate,
+            holdoff_timeout_usec: 30_000_000,
+            idle_action: HandleAction::Ignore,
+            idle_action_usec: 1_800_000_000,
+            idle_hint: false,
+            idle_since_hint: 0,
+            idle_since_hint_monotonic: 0,
+            inhibit_delay_max_usec: 5_000_000,
+            inhibitors_max: 8192,
+            kill_exclude_users: vec!["root".to_string()],
+            kill_only_users: Vec::new(),
+            kill_user_processes: false,
+            lid_closed: false,
+            n_auto_vts: 6,
+            on_external_power: true,
+            preparing_for_shutdown: false,
+            preparing_for_sleep: false,
+            reboot_parameter: String::new(),
+            reboot_to_boot_loader_entry: String::new(),
+            reboot_to_boot_loader_menu: u64::MAX,
+            reboot_to_firmware_setup: false,
+            remove_ipc: true,
+            runtime_directory_inodes_max: 1_048_576,
+            runtime_directory_size: 1_638_400_000,
+            scheduled_shutdown: (String::new(), 0),
+            sessions_max: 8192,
+            sleep_operation: vec![
+                SleepMode::SuspendThenHibernate,
+                SleepMode::Suspend,
+                SleepMode::Hibernate,
+            ],
+            stop_idle_session_usec: u64::MAX,
+            user_stop_delay_usec: 10_000_000,
+            wall_message: String::new(),
+            missing: Vec::new(),
+        }
+    }
+}
+
+/// A session as the fake reports it. `Active` is derived from `state`, and the session shows up
+/// in the `Sessions` of its seat and user.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FakeSession {
+    pub id: String,
+    pub uid: u32,
+    pub seat: Option<String>,
+    pub class: SessionClass,
+    pub type_: SessionType,
+    pub state: SessionState,
+    pub audit: u32,
+    pub desktop: String,
+    pub display: String,
+    pub idle_hint: bool,
+    pub idle_since_hint: u64,
+    pub idle_since_hint_monotonic: u64,
+    pub leader: u32,
+    pub locked_hint: bool,
+    pub remote: bool,
+    pub remote_host: String,
+    pub remote_user: String,
+    pub scope: String,
+    pub service: String,
+    pub timestamp: u64,
+    pub timestamp_monotonic: u64,
+    pub tty: String,
+    pub vtnr: u32,
+}
+
+impl FakeSession {
+    /// An online TTY session of `uid`, not attached to any seat
+    pub fn new(id: impl Into<String>, uid: u32) -> Self {
+        let id = id.into();
+        Self {
+            scope: format!("session-{id}.scope"),
+            id,
+            uid,
+            seat: None,
+            class: SessionClass::User,
+            type_: SessionType::TTY,
+            state: SessionState::Online,
+            audit: 0,
+            desktop: String::new(),
+            display: String::new(),
+            idle_hint: false,
+            idle_since_hint: 0,
+            idle_since_hint_monotonic: 0,
+            leader: 1,
+            locked_hint: false,
+            remote: false,
+            remote_host: String::new(),
+            remote_user: String::new(),
+            service: "login".to_string(),
+            timestamp: realtime_now(),
+            timestamp_monotonic: 1_000_000,
+            tty: String::new(),
+            vtnr: 0,
+        }
+    }
+}
+
+/// A seat. Its `ActiveSession` is the first active session found on it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FakeSeat {
+    pub id: String,
+    pub can_graphical: bool,
+    pub can_tty: bool,
+    pub idle_hint: bool,
+    pub idle_since_hint: u64,
+    pub idle_since_hint_monotonic: u64,
+}
+
+impl FakeSeat {
+    pub fn new(id: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            can_graphical: true,
+            can_tty: true,
+            idle_hint: false,
+            idle_since_hint: 0,
+            idle_since_hint_monotonic: 0,
+        }
+    }
+}
+
+/// A user. `State` and `Display` are derived from the user's sessions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FakeUser {
+    pub uid: u32,
+    pub gid: u32,
+    pub name: String,
+    pub idle_hint: bool,
+    pub idle_since_hint: u64,
+    pub idle_since_hint_monotonic: u64,
+    pub linger: bool,
+    pub runtime_path: String,
+    pub service: String,
+    pub slice: String,
+    pub timestamp: u64,
+    pub timestamp_monotonic: u64,
+}
+
+impl FakeUser {
+    pub fn new(uid: u32, name: impl Into<String>) -> Self {
+        Self {
+            uid,
+            gid: uid,
+            name: name.into(),
+            idle_hint: false,
+            idle_since_hint: 0,
+            idle_since_hint_monotonic: 0,
+            linger: false,
+            runtime_path: format!("/run/user/{uid}"),
+            service: format!("user@{uid}.service"),
+            slice: format!("user-{uid}.slice"),
+            timestamp: realtime_now(),
+            timestamp_monotonic: 1_000_000,
+        }
+    }
+}
+
+/// An entry of `ListInhibitors`
+#[derive(Debug, Clone, PartialEq)]
+pub struct FakeInhibitor {
+    pub what: InhibitTypes,
+    pub who: String,
+    pub why: String,
+    pub mode: Mode,
+    pub uid: u32,
+    pub pid: u32,
+}
+
+/// A method call received by the fake that would have changed something on a real machine
+#[derive(Debug, Clone, PartialEq)]
+pub struct FakeCall {
+    /// Interface member, for example `Suspend`
+    pub member: String,
+    /// Object the call was made on
+    pub path: OwnedObjectPath,
+    /// The call arguments in order
+    pub args: Vec<OwnedValue>,
+}
+
+#[derive(Default)]
+struct Shared {
+    state: Mutex<State>,
+}
+
+impl Shared {
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Apply `f` to the state, then bring the object server in line with it and emit every
+    /// signal a real logind would have sent for the change
+    async fn update<F, R>(shared: &Arc<Self>, conn: &Connection, f: F) -> zbus::Result<R>
+    where
+        F: FnOnce(&mut State) -> R,
+    {
+        let before = Self::snapshot(shared).await;
+        let res = f(&mut shared.state());
+        let after = Self::snapshot(shared).await;
+
+        let server = conn.object_server();
+        // Sessions go before the seats and users they belong to, and come after them when new
+        for (key, (kind, _)) in before.iter().rev().filter(|(k, _)| !after.contains_key(*k)) {
+            let ctxt = SignalContext::new(conn, MANAGER_PATH)?;
+            let path = ObjectPath::try_from(key.1.as_str())?;
+            match kind {
+                ObjectKind::Session(id) => {
+                    server.remove::<SessionIface, _>(&path).await?;
+                    ManagerIface::session_removed(&ctxt, id, path).await?;
+                }
+                ObjectKind::Seat(id) => {
+                    server.remove::<SeatIface, _>(&path).await?;
+                    ManagerIface::seat_removed(&ctxt, id, path).await?;
+                }
+                ObjectKind::User(uid) => {
+                    server.remove::<UserIface, _>(&path).await?;
+                    ManagerIface::user_removed(&ctxt, *uid, path).await?;
+                }
+                ObjectKind::Manager => {}
+            }
+        }
+        for (key, (kind, _)) in after.iter().filter(|(k, _)| !before.contains_key(*k)) {
+            let ctxt = SignalContext::new(conn, MANAGER_PATH)?;
+            let path = ObjectPath::try_from(key.1.as_str())?;
+            match kind {
+                ObjectKind::Session(id) => {
+                    server
+                        .at(&path, SessionIface::new(shared.clone(), id))
+                        .await?;
+                    ManagerIface::session_new(&ctxt, id, path).await?;
+                }
+                ObjectKind::Seat(id) => {
+                    server.at(&path, SeatIface::new(shared.clone(), id)).await?;
+                    ManagerIface::seat_new(&ctxt, id, path).await?;
+                }
+                ObjectKind::User(uid) => {
+                    server
+                        .at(&path, UserIface::new(shared.clone(), *uid))
+                        .await?;
+                    ManagerIface::user_new(&ctxt, *uid, path).await?;
+                }
+                ObjectKind::Manager => {}
+            }
+        }
+        for (key, (kind, new)) in &after {
+            let old = match before.get(key) {
+                Some((_, old)) => old,
+                None => continue,
+            };
+            let changed: HashMap<&str, &Value<'_>> = new
+                .iter()
+                .filter(|(name, value)| old.get(*name) != Some(*value))
+                .filter(|(name, _)| kind.emits_changed(name))
+                .map(|(name, value)| (name.as_str(), &**value))
+                .collect();
+            if !changed.is_empty() {
+                let ctxt = SignalContext::new(conn, key.1.as_str())?;
+                fdo::Properties::properties_changed(&ctxt, kind.interface(), &changed, &[]).await?;
+            }
+        }
+
+        Ok(res)
+    }
+
+    async fn snapshot(shared: &Arc<Self>) -> Snapshot {
+        let (sessions, seats, users) = {
+            let state = shared.state();
+            (
+                state.sessions.keys().cloned().collect::<Vec<_>>(),
+                state.seats.keys().cloned().collect::<Vec<_>>(),
+                state.users.keys().copied().collect::<Vec<_>>(),
+            )
+        };
+
+        let mut objects = BTreeMap::new();
+        let manager = ManagerIface::new(shared.clone()).get_all().await;
+        objects.insert(
+            (0, MANAGER_PATH.to_string()),
+            (ObjectKind::Manager, manager),
+        );
+        for id in seats {
+            let props = SeatIface::new(shared.clone(), &id).get_all().await;
+            let key = (1, seat_path(&id).to_string());
+            objects.insert(key, (ObjectKind::Seat(id), props));
+        }
+        for uid in users {
+            let props = UserIface::new(shared.clone(), uid).get_all().await;
+            let key = (2, user_path(uid).to_string());
+            objects.insert(key, (ObjectKind::User(uid), props));
+        }
+        for id in sessions {
+            let props = SessionIface::new(shared.clone(), &id).get_all().await;
+            let key = (3, session_path(&id).to_string());
+            objects.insert(key, (ObjectKind::Session(id), props));
+        }
+        objects
+    }
+
+    /// Hold `stream` on a thread until the client closes its end, then run `on_close`
+    fn watch_fd<F>(shared: &Arc<Self>, conn: &Connection, mut stream: UnixStream, on_close: F)
+    where
+        F: FnOnce(&mut State) + Send + 'static,
+    {
+        let shared = shared.clone();
+        let conn = conn.clone();
+        std::thread::spawn(move || {
+            let mut buf = [0u8; 16];
+            while matches!(stream.read(&mut buf), Ok(n) if n > 0) {}
+            zbus::block_on(Shared::update(&shared, &conn, on_close)).ok();
+        });
+    }
+}
+
+type Properties = HashMap<String, OwnedValue>;
+
+/// Properties of every object, keyed by a rank ordering the manager, seats, users and sessions,
+/// then by object path
+type Snapshot = BTreeMap<(u8, String), (ObjectKind, Properties)>;
+
+enum ObjectKind {
+    Manager,
+    Session(String),
+    Seat(String),
+    User(u32),
+}
+
+impl ObjectKind {
+    /// Whether logind sends `PropertiesChanged` for `property`, which it does only for those
+    /// marked `SD_BUS_VTABLE_PROPERTY_EMITS_CHANGE` in its vtables
+    fn emits_changed(&self, property: &str) -> bool {
+        let emitting: &[&str] = match self {
+            ObjectKind::Manager => &["IdleHint", "IdleSinceHint", "IdleSinceHintMonotonic"],
+            ObjectKind::Session(_) => &[
+                "TTY",
+                "Display",
+                "Type",
+                "Class",
+                "Active",
+                "IdleHint",
+                "IdleSinceHint",
+                "IdleSinceHintMonotonic",
+                "LockedHint",
+            ],
+            ObjectKind::Seat(_) => &[
+                "ActiveSession",
+                "CanGraphical",
+                "IdleHint",
+                "IdleSinceHint",
+                "IdleSinceHintMonotonic",
+            ],
+            ObjectKind::User(_) => &[
+                "Display",
+                "IdleHint",
+                "IdleSinceHint",
+                "IdleSinceHintMonotonic",
+            ],
+        };
+        emitting.contains(&property)
+    }
+
+    fn interface(&self) -> zbus::names::InterfaceName<'static> {
+        match self {
+            ObjectKind::Manager => ManagerIface::name(),
+            ObjectKind::Session(_) => SessionIface::name(),
+            ObjectKind::Seat(_) => SeatIface::name(),
+            ObjectKind::User(_) => UserIface::name(),
+        }
+    }
+}
+
+#[derive(Default)]
+struct State {
+    manager: FakeManager,
+    sessions: BTreeMap<String, FakeSession>,
+    seats: BTreeMap<String, FakeSeat>,
+    users: BTreeMap<u32, FakeUser>,
+    inhibitors: Vec<HeldInhibitor>,
+    next_inhibitor: u64,
+    next_session: u64,
+    calls: Vec<FakeCall>,
+    /// Sessions with a controller, from `TakeControl`
+    controlled: BTreeSet<String>,
+    /// `(session id, major, minor)` of devices taken by controllers
+    devices: BTreeSet<(String, u32, u32)>,
+}
+
+struct HeldInhibitor {
+    id: u64,
+    inhibitor: FakeInhibitor,
+}
+
+impl State {
+    fn insert_session(&mut self, session: FakeSession) {
+        self.users
+            .entry(session.uid)
+            .or_insert_with(|| FakeUser::new(session.uid, format!("user{}", session.uid)));
+        if let Some(seat) = &session.seat {
+            self.seats
+                .entry(seat.clone())
+                .or_insert_with(|| FakeSeat::new(seat.clone()));
+        }
+        self.sessions.insert(session.id.clone(), session);
+    }
+
+    fn push_inhibitor(&mut self, inhibitor: FakeInhibitor) -> u64 {
+        self.next_inhibitor += 1;
+        self.inhibitors.push(HeldInhibitor {
+            id: self.next_inhibitor,
+            inhibitor,
+        });
+        self.next_inhibitor
+    }
+
+    fn record(&mut self, path: &ObjectPath<'_>, member: &str, args: Vec<OwnedValue>) {
+        self.calls.push(FakeCall {
+            member: member.to_string(),
+            path: path.to_owned().into(),
+            args,
+        });
+    }
+
+    fn session(&self, id: &str) -> Result<&FakeSession, Error> {
+        self.sessions
+            .get(id)
+            .ok_or_else(|| Error::no_such_session(id))
+    }
+
+    fn seat(&self, id: &str) -> Result<&FakeSeat, Error> {
+        self.seats.get(id).ok_or_else(|| Error::no_such_seat(id))
+    }
+
+    fn user(&self, uid: u32) -> Result<&FakeUser, Error> {
+        self.users.get(&uid).ok_or_else(|| Error::no_such_user(uid))
+    }
+
+    fn active_session(&self, seat: &str) -> Option<&FakeSession> {
+        self.sessions
+            .values()
+            .find(|s| s.seat.as_deref() == Some(seat) && s.state == SessionState::Active)
+    }
+
+    fn inhibited(&self, mode: Mode) -> InhibitTypes {
+        self.inhibitors
+            .iter()
+            .filter(|h| h.inhibitor.mode == mode)
+            .flat_map(|h| h.inhibitor.what.iter().cloned())
+            .collect()
+    }
+}
+
+/// A D-Bus error reply using the same names logind does
+#[derive(Debug)]
+pub(crate) struct Error {
+    name: &'static str,
+    message: String,
+}
+
+impl Error {
+    fn new(name: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            name,
+            message: message.into(),
+        }
+    }
+
+    fn no_such_session(id: &str) -> Self {
+        Self::new(
+            "org.freedesktop.login1.NoSuchSession",
+            format!("No session '{id}' known"),
+        )
+    }
+
+    fn no_such_seat(id: &str) -> Self {
+        Self::new(
+            "org.freedesktop.login1.NoSuchSeat",
+            format!("No seat '{id}' known"),
+        )
+    }
+
+    fn no_such_user(uid: u32) -> Self {
+        Self::new(
+            "org.freedesktop.login1.NoSuchUser",
+            format!("User ID {uid} is not logged in or lingering"),
+        )
+    }
+
+    fn not_in_control() -> Self {
+        Self::new(
+            "org.freedesktop.login1.NotInControl",
+            "You are not in control of this session",
+        )
+    }
+
+    fn device_is_taken() -> Self {
+        Self::new(
+            "org.freedesktop.login1.DeviceIsTaken",
+            "Device already taken",
+        )
+    }
+
+    fn device_not_taken() -> Self {
+        Self::new("org.freedesktop.login1.DeviceNotTaken", "Device not taken")
+    }
+
+    fn unknown_method(member: &str) -> Self {
+        Self::new(
+            "org.freedesktop.DBus.Error.UnknownMethod",
+            format!("Unknown method {member}"),
+        )
+    }
+
+    fn invalid_args(message: impl Into<String>) -> Self {
+        Self::new("org.freedesktop.DBus.Error.InvalidArgs", message)
+    }
+}
+
+impl From<zbus::Error> for Error {
+    fn from(e: zbus::Error) -> Self {
+        Self::new("org.freedesktop.DBus.Error.Failed", e.to_string())
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.name, self.message)
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl zbus::DBusError for Error {
+    fn name(&self) -> ErrorName<'_> {
+        ErrorName::from_static_str_unchecked(self.name)
+    }
+
+    fn description(&self) -> Option<&str> {
+        Some(&self.message)
+    }
+
+    fn create_reply(&self, call: &MessageHeader<'_>) -> zbus::Result<zbus::Message> {
+        zbus::MessageBuilder::error(call, self.name())?.build(&(self.message.as_str(),))
+    }
+}
+
+fn realtime_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_micros() as u64)
+        .unwrap_or_default()
+}
+
+/// Escape a label for use as an object path element the way sd-bus does
+fn bus_label_escape(label: &str) -> String {
+    let mut escaped = String::with_capacity(label.len());
+    for (i, b) in label.bytes().enumerate() {
+        if b.is_ascii_alphabetic() || (i > 0 && b.is_ascii_digit()) {
+            escaped.push(b as char);
+        } else {
+            escaped.push_str(&format!("_{b:02x}"));
+        }
+    }
+    if escaped.is_empty() {
+        escaped.push('_');
+    }
+    escaped
+}
+
+fn session_path(id: &str) -> OwnedObjectPath {
+    OwnedObjectPath::try_from(format!("{MANAGER_PATH}/session/{}", bus_label_escape(id))).unwrap()
+}
+
+fn seat_path(id: &str) -> OwnedObjectPath {
+    OwnedObjectPath::try_from(format!("{MANAGER_PATH}/seat/{}", bus_label_escape(id))).unwrap()
+}
+
+fn user_path(uid: u32) -> OwnedObjectPath {
+    OwnedObjectPath::try_from(format!("{MANAGER_PATH}/user/_{uid}")).unwrap()
+}
+
+fn root_path() -> OwnedObjectPath {
+    OwnedObjectPath::try_from("/").unwrap()
+}
+
+fn value<'a, T: Into<Value<'a>>>(v: T) -> OwnedValue {
+    v.into().into()
+}
+
+/// A fake with `seat0`, user 1000 and an active Wayland session `2` on it
+#[cfg(test)]
+pub(crate) async fn desktop() -> FakeLogind {
+    let fake = FakeLogind::new().await.unwrap();
+    fake.add_user(FakeUser::new(1000, "user")).await.unwrap();
+    fake.add_seat(FakeSeat::new("seat0")).await.unwrap();
+    fake.add_session(FakeSession {
+        seat: Some("seat0".to_string()),
+        type_: SessionType::Wayland,
+        state: SessionState::Active,
+        desktop: "pop".to_string(),
+        leader: 1234,
+        service: "gdm-password".to_string(),
+        tty: "tty2".to_string(),
+        vtnr: 2,
+        ..FakeSession::new("2", 1000)
+    })
+    .await
+    .unwrap();
+    fake
+}
+
+fn user_state(state: &State, uid: u32) -> UserState {
+    let user = match state.users.get(&uid) {
+        Some(user) => user,
+        None => return UserState::Offline,
+    };
+    let mut sessions = state.sessions.values().filter(|s| s.uid == uid).peekable();
+    if sessions.peek().is_none() {
+        return if user.linger {
+            UserState::Lingering
+        } else {
+            UserState::Offline
+        };
+    }
+    if sessions.any(|s| s.state == SessionState::Active) {
+        UserState::Active
+    } else {
+        UserState::Online
+    }
+}
@@ -0,0 +1,159 @@
+//! `org.freedesktop.login1.Seat` as served by [`super::FakeLogind`]
+
+use std::sync::Arc;
+
+use zbus::{dbus_interface, fdo, zvariant::OwnedObjectPath, Connection};
+
+use super::{root_path, seat_path, session_path, value, Error, FakeSeat, Shared};
+
+pub(super) struct SeatIface {
+    shared: Arc<Shared>,
+    id: String,
+}
+
+impl SeatIface {
+    pub(super) fn new(shared: Arc<Shared>, id: &str) -> Self {
+        Self {
+            shared,
+            id: id.to_string(),
+        }
+    }
+
+    fn get<T>(&self, f: impl FnOnce(&FakeSeat) -> T) -> fdo::Result<T> {
+        let state = self.shared.state();
+        let seat = state
+            .seats
+            .get(&self.id)
+            .ok_or_else(|| fdo::Error::UnknownObject(format!("No seat '{}'", self.id)))?;
+        Ok(f(seat))
+    }
+
+    /// Activate the session with the next or previous VT number on this seat
+    async fn switch_by(&self, conn: &Connection, forward: bool) -> Result<(), Error> {
+        let id = self.id.as_str();
+        Shared::update(&self.shared, conn, |state| {
+            let mut vts: Vec<(u32, String)> = state
+                .sessions
+                .values()
+                .filter(|s| s.seat.as_deref() == Some(id) && s.vtnr > 0)
+                .map(|s| (s.vtnr, s.id.clone()))
+                .collect();
+            vts.sort();
+            if vts.is_empty() {
+                return Ok(());
+            }
+            let current = state.active_session(id).map(|s| s.vtnr).unwrap_or(0);
+            let pos = vts.iter().position(|(vt, _)| *vt == current);
+            let next = match (pos, forward) {
+                (Some(p), true) => (p + 1) % vts.len(),
+                (Some(p), false) => (p + vts.len() - 1) % vts.len(),
+                (None, _) => 0,
+            };
+            super::manager::activate(state, &vts[next].1, Some(id))
+        })
+        .await?
+    }
+}
+
+#[dbus_interface(name = "org.freedesktop.login1.Seat")]
+impl SeatIface {
+    async fn activate_session(
+        &self,
+        #[zbus(connection)] conn: &Connection,
+        session_id: &str,
+    ) -> Result<(), Error> {
+        let id = self.id.as_str();
+        Shared::update(&self.shared, conn, |state| {
+            super::manager::activate(state, session_id, Some(id))
+        })
+        .await?
+    }
+
+    async fn switch_to(
+        &self,
+        #[zbus(connection)] conn: &Connection,
+        vtnr: u32,
+    ) -> Result<(), Error> {
+        let id = self.id.as_str();
+        Shared::update(&self.shared, conn, |state| {
+            let session = state
+                .sessions
+                .values()
+                .find(|s| s.seat.as_deref() == Some(id) && s.vtnr == vtnr)
+                .map(|s| s.id.clone());
+            match session {
+                Some(session) => super::manager::activate(state, &session, Some(id)),
+                None => Ok(()),
+            }
+        })
+        .await?
+    }
+
+    async fn switch_to_next(&self, #[zbus(connection)] conn: &Connection) -> Result<(), Error> {
+        self.switch_by(conn, true).await
+    }
+
+    async fn switch_to_previous(&self, #[zbus(connection)] conn: &Connection) -> Result<(), Error> {
+        self.switch_by(conn, false).await
+    }
+
+    async fn terminate(&self, #[zbus(connection)] conn: &Connection) -> Result<(), Error> {
+        let id = self.id.as_str();
+        Shared::update(&self.shared, conn, |state| {
+            state.record(&seat_path(id), "Terminate", vec![value(id)]);
+            state.sessions.retain(|_, s| s.seat.as_deref() != Some(id));
+        })
+        .await?;
+        Ok(())
+    }
+
+    #[dbus_interface(property)]
+    fn active_session(&self) -> (String, OwnedObjectPath) {
+        self.shared
+            .state()
+            .active_session(&self.id)
+            .map(|s| (s.id.clone(), session_path(&s.id)))
+            .unwrap_or_else(|| (String::new(), root_path()))
+    }
+
+    #[dbus_interface(property)]
+    fn can_graphical(&self) -> fdo::Result<bool> {
+        self.get(|s| s.can_graphical)
+    }
+
+    #[dbus_interface(property, name = "CanTTY")]
+    fn can_tty(&self) -> fdo::Result<bool> {
+        self.get(|s| s.can_tty)
+    }
+
+    #[dbus_interface(property)]
+    fn id(&self) -> String {
+        self.id.clone()
+    }
+
+    #[dbus_interface(property)]
+    fn idle_hint(&self) -> fdo::Result<bool> {
+        self.get(|s| s.idle_hint)
+    }
+
+    #[dbus_interface(property)]
+    fn idle_since_hint(&self) -> fdo::Result<u64> {
+        self.get(|s| s.idle_since_hint)
+    }
+
+    #[dbus_interface(property)]
+    fn idle_since_hint_monotonic(&self) -> fdo::Result<u64> {
+        self.get(|s| s.idle_since_hint_monotonic)
+    }
+
+    #[dbus_interface(property)]
+    fn sessions(&self) -> Vec<(String, OwnedObjectPath)> {
+        self.shared
+            .state()
+            .sessions
+            .values()
+            .filter(|s| s.seat.as_deref() == Some(self.id.as_str()))
+            .map(|s| (s.id.clone(), session_path(&s.id)))
+            .collect()
+    }
+}
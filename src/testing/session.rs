@@ -0,0 +1,351 @@
+//! `org.freedesktop.login1.Session` as served by [`super::FakeLogind`]
+
+use std::{
+    fs::File,
+    os::unix::io::{FromRawFd, IntoRawFd},
+    sync::Arc,
+};
+
+use zbus::{
+    dbus_interface, fdo,
+    zvariant::{self, OwnedObjectPath},
+    Connection, SignalContext,
+};
+
+use super::{root_path, seat_path, session_path, user_path, value, Error, FakeSession, Shared};
+use crate::session::{SessionState, SessionType};
+
+pub(super) struct SessionIface {
+    shared: Arc<Shared>,
+    id: String,
+}
+
+impl SessionIface {
+    pub(super) fn new(shared: Arc<Shared>, id: &str) -> Self {
+        Self {
+            shared,
+            id: id.to_string(),
+        }
+    }
+
+    fn get<T>(&self, f: impl FnOnce(&FakeSession) -> T) -> fdo::Result<T> {
+        let state = self.shared.state();
+        let session = state
+            .sessions
+            .get(&self.id)
+            .ok_or_else(|| fdo::Error::UnknownObject(format!("No session '{}'", self.id)))?;
+        Ok(f(session))
+    }
+
+    async fn update(
+        &self,
+        conn: &Connection,
+        f: impl FnOnce(&mut FakeSession),
+    ) -> Result<(), Error> {
+        let id = self.id.as_str();
+        Shared::update(&self.shared, conn, |state| {
+            match state.sessions.get_mut(id) {
+                Some(session) => {
+                    f(session);
+                    Ok(())
+                }
+                None => Err(Error::no_such_session(id)),
+            }
+        })
+        .await?
+    }
+}
+
+#[dbus_interface(name = "org.freedesktop.login1.Session")]
+impl SessionIface {
+    async fn activate(&self, #[zbus(connection)] conn: &Connection) -> Result<(), Error> {
+        let id = self.id.as_str();
+        Shared::update(&self.shared, conn, |state| {
+            super::manager::activate(state, id, None)
+        })
+        .await?
+    }
+
+    fn kill(&self, who: &str, signal_number: i32) {
+        self.shared.state().record(
+            &session_path(&self.id),
+            "Kill",
+            vec![value(who), value(signal_number)],
+        );
+    }
+
+    #[dbus_interface(name = "Lock")]
+    async fn lock_method(
+        &self,
+        #[zbus(signal_context)] ctxt: SignalContext<'_>,
+    ) -> Result<(), Error> {
+        Ok(Self::lock(&ctxt).await?)
+    }
+
+    fn pause_device_complete(&self, major: u32, minor: u32) {
+        self.shared.state().record(
+            &session_path(&self.id),
+            "PauseDeviceComplete",
+            vec![value(major), value(minor)],
+        );
+    }
+
+    fn release_control(&self) {
+        let mut state = self.shared.state();
+        state.controlled.remove(&self.id);
+        state.devices.retain(|(session, _, _)| *session != self.id);
+        state.record(&session_path(&self.id), "ReleaseControl", Vec::new());
+    }
+
+    fn release_device(&self, major: u32, minor: u32) -> Result<(), Error> {
+        let mut state = self.shared.state();
+        if !state.devices.remove(&(self.id.clone(), major, minor)) {
+            return Err(Error::device_not_taken());
+        }
+        state.record(
+            &session_path(&self.id),
+            "ReleaseDevice",
+            vec![value(major), value(minor)],
+        );
+        Ok(())
+    }
+
+    fn set_brightness(&self, subsystem: &str, name: &str, brightness: u32) {
+        self.shared.state().record(
+            &session_path(&self.id),
+            "SetBrightness",
+            vec![value(subsystem), value(name), value(brightness)],
+        );
+    }
+
+    async fn set_idle_hint(
+        &self,
+        #[zbus(connection)] conn: &Connection,
+        idle: bool,
+    ) -> Result<(), Error> {
+        self.update(conn, |s| s.idle_hint = idle).await
+    }
+
+    async fn set_locked_hint(
+        &self,
+        #[zbus(connection)] conn: &Connection,
+        locked: bool,
+    ) -> Result<(), Error> {
+        self.update(conn, |s| s.locked_hint = locked).await
+    }
+
+    async fn set_type(
+        &self,
+        #[zbus(connection)] conn: &Connection,
+        type_: &str,
+    ) -> Result<(), Error> {
+        let type_ = SessionType::from(type_);
+        if let SessionType::Other(_) = type_ {
+            return Err(Error::invalid_args(format!(
+                "Invalid session type '{type_}'"
+            )));
+        }
+        self.update(conn, |s| s.type_ = type_).await
+    }
+
+    fn take_control(&self, force: bool) {
+        let mut state = self.shared.state();
+        state.controlled.insert(self.id.clone());
+        state.record(&session_path(&self.id), "TakeControl", vec![value(force)]);
+    }
+
+    /// Every device is backed by `/dev/null`
+    fn take_device(&self, major: u32, minor: u32) -> Result<(zvariant::OwnedFd, bool), Error> {
+        let mut state = self.shared.state();
+        if !state.controlled.contains(&self.id) {
+            return Err(Error::not_in_control());
+        }
+        if !state.devices.insert((self.id.clone(), major, minor)) {
+            return Err(Error::device_is_taken());
+        }
+        let inactive = state.session(&self.id)?.state != SessionState::Active;
+        state.record(
+            &session_path(&self.id),
+            "TakeDevice",
+            vec![value(major), value(minor)],
+        );
+        let file = File::open("/dev/null").map_err(|e| Error::from(zbus::Error::from(e)))?;
+        // SAFETY: the fd was just opened and is owned by nothing else
+        let fd = unsafe { zvariant::OwnedFd::from_raw_fd(file.into_raw_fd()) };
+        Ok((fd, inactive))
+    }
+
+    async fn terminate(&self, #[zbus(connection)] conn: &Connection) -> Result<(), Error> {
+        let id = self.id.as_str();
+        Shared::update(&self.shared, conn, |state| {
+            state.sessions.remove(id);
+        })
+        .await?;
+        Ok(())
+    }
+
+    #[dbus_interface(name = "Unlock")]
+    async fn unlock_method(
+        &self,
+        #[zbus(signal_context)] ctxt: SignalContext<'_>,
+    ) -> Result<(), Error> {
+        Ok(Self::unlock(&ctxt).await?)
+    }
+
+    #[dbus_interface(signal)]
+    pub(super) async fn lock(ctxt: &SignalContext<'_>) -> zbus::Result<()>;
+
+    #[dbus_interface(signal)]
+    pub(super) async fn unlock(ctxt: &SignalContext<'_>) -> zbus::Result<()>;
+
+    #[dbus_interface(signal)]
+    pub(super) async fn pause_device(
+        ctxt: &SignalContext<'_>,
+        major: u32,
+        minor: u32,
+        type_: &str,
+    ) -> zbus::Result<()>;
+
+    #[dbus_interface(signal)]
+    pub(super) async fn resume_device(
+        ctxt: &SignalContext<'_>,
+        major: u32,
+        minor: u32,
+        fd: zvariant::Fd,
+    ) -> zbus::Result<()>;
+
+    #[dbus_interface(property)]
+    fn active(&self) -> fdo::Result<bool> {
+        self.get(|s| s.state == SessionState::Active)
+    }
+
+    #[dbus_interface(property)]
+    fn audit(&self) -> fdo::Result<u32> {
+        self.get(|s| s.audit)
+    }
+
+    #[dbus_interface(property)]
+    fn class(&self) -> fdo::Result<String> {
+        self.get(|s| s.class.to_string())
+    }
+
+    #[dbus_interface(property)]
+    fn desktop(&self) -> fdo::Result<String> {
+        self.get(|s| s.desktop.clone())
+    }
+
+    #[dbus_interface(property)]
+    fn display(&self) -> fdo::Result<String> {
+        self.get(|s| s.display.clone())
+    }
+
+    #[dbus_interface(property)]
+    fn id(&self) -> String {
+        self.id.clone()
+    }
+
+    #[dbus_interface(property)]
+    fn idle_hint(&self) -> fdo::Result<bool> {
+        self.get(|s| s.idle_hint)
+    }
+
+    #[dbus_interface(property)]
+    fn idle_since_hint(&self) -> fdo::Result<u64> {
+        self.get(|s| s.idle_since_hint)
+    }
+
+    #[dbus_interface(property)]
+    fn idle_since_hint_monotonic(&self) -> fdo::Result<u64> {
+        self.get(|s| s.idle_since_hint_monotonic)
+    }
+
+    #[dbus_interface(property)]
+    fn leader(&self) -> fdo::Result<u32> {
+        self.get(|s| s.leader)
+    }
+
+    #[dbus_interface(property)]
+    fn locked_hint(&self) -> fdo::Result<bool> {
+        self.get(|s| s.locked_hint)
+    }
+
+    #[dbus_interface(property)]
+    fn name(&self) -> fdo::Result<String> {
+        let uid = self.get(|s| s.uid)?;
+        Ok(self
+            .shared
+            .state()
+            .users
+            .get(&uid)
+            .map(|u| u.name.clone())
+            .unwrap_or_default())
+    }
+
+    #[dbus_interface(property)]
+    fn remote(&self) -> fdo::Result<bool> {
+        self.get(|s| s.remote)
+    }
+
+    #[dbus_interface(property)]
+    fn remote_host(&self) -> fdo::Result<String> {
+        self.get(|s| s.remote_host.clone())
+    }
+
+    #[dbus_interface(property)]
+    fn remote_user(&self) -> fdo::Result<String> {
+        self.get(|s| s.remote_user.clone())
+    }
+
+    #[dbus_interface(property)]
+    fn scope(&self) -> fdo::Result<String> {
+        self.get(|s| s.scope.clone())
+    }
+
+    #[dbus_interface(property)]
+    fn seat(&self) -> fdo::Result<(String, OwnedObjectPath)> {
+        self.get(|s| match &s.seat {
+            Some(seat) => (seat.clone(), seat_path(seat)),
+            None => (String::new(), root_path()),
+        })
+    }
+
+    #[dbus_interface(property)]
+    fn service(&self) -> fdo::Result<String> {
+        self.get(|s| s.service.clone())
+    }
+
+    #[dbus_interface(property)]
+    fn state(&self) -> fdo::Result<String> {
+        self.get(|s| s.state.to_string())
+    }
+
+    #[dbus_interface(property, name = "TTY")]
+    fn tty(&self) -> fdo::Result<String> {
+        self.get(|s| s.tty.clone())
+    }
+
+    #[dbus_interface(property)]
+    fn timestamp(&self) -> fdo::Result<u64> {
+        self.get(|s| s.timestamp)
+    }
+
+    #[dbus_interface(property)]
+    fn timestamp_monotonic(&self) -> fdo::Result<u64> {
+        self.get(|s| s.timestamp_monotonic)
+    }
+
+    #[dbus_interface(property, name = "Type")]
+    fn type_(&self) -> fdo::Result<String> {
+        self.get(|s| s.type_.to_string())
+    }
+
+    #[dbus_interface(property)]
+    fn user(&self) -> fdo::Result<(u32, OwnedObjectPath)> {
+        self.get(|s| (s.uid, user_path(s.uid)))
+    }
+
+    #[dbus_interface(property, name = "VTNr")]
+    fn vtnr(&self) -> fdo::Result<u32> {
+        self.get(|s| s.vtnr)
+    }
+}
@@ -0,0 +1,108 @@
+use crate::manager::{InhibitType, InhibitTypes, ManagerProxy, Mode};
+use crate::session::{SessionProxy, SessionState};
+use futures_lite::{future, StreamExt};
+use zbus::fdo::PropertiesProxy;
+
+use super::{desktop, FakeInhibitor, FakeLogind, FakeSession};
+
+#[test]
+fn session_signals() {
+    future::block_on(async {
+        let fake = FakeLogind::new().await.unwrap();
+        let manager = ManagerProxy::new(fake.connection()).await.unwrap();
+        let mut new = manager.receive_session_new().await.unwrap();
+        let mut removed = manager.receive_session_removed().await.unwrap();
+
+        fake.add_session(FakeSession::new("c1", 1000))
+            .await
+            .unwrap();
+        let signal = new.next().await.unwrap();
+        assert_eq!(*signal.args().unwrap().session_id(), "c1");
+        assert_eq!(manager.list_users().await.unwrap()[0].uid(), 1000);
+
+        fake.remove_session("c1").await.unwrap();
+        let signal = removed.next().await.unwrap();
+        assert_eq!(
+            signal.args().unwrap().object_path().as_str(),
+            "/org/freedesktop/login1/session/c1"
+        );
+        assert!(manager.list_sessions().await.unwrap().is_empty());
+    })
+}
+
+#[test]
+fn property_changes() {
+    future::block_on(async {
+        let fake = desktop().await;
+        let session = SessionProxy::builder(fake.connection())
+            .path("/org/freedesktop/login1/session/_32")
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+        assert!(session.active().await.unwrap());
+
+        let mut changes = session.receive_active_changed().await;
+        fake.update_session("2", |s| s.state = SessionState::Online)
+            .await
+            .unwrap();
+        let change = changes.next().await.unwrap();
+        assert!(!change.get().await.unwrap());
+        assert!(!session.active().await.unwrap());
+    })
+}
+
+#[test]
+fn unannounced_properties() {
+    future::block_on(async {
+        let fake = desktop().await;
+        let properties = PropertiesProxy::builder(fake.connection())
+            .destination("org.freedesktop.login1")
+            .unwrap()
+            .path("/org/freedesktop/login1")
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+        let mut changes = properties.receive_properties_changed().await.unwrap();
+
+        // logind sends nothing for the inhibitor properties
+        fake.add_inhibitor(player()).await.unwrap();
+        fake.update_manager(|m| m.idle_hint = true).await.unwrap();
+        let signal = changes.next().await.unwrap();
+        let args = signal.args().unwrap();
+        let changed: Vec<_> = args.changed_properties().keys().copied().collect();
+        assert_eq!(changed, ["IdleHint"]);
+    })
+}
+
+fn player() -> FakeInhibitor {
+    FakeInhibitor {
+        what: InhibitTypes::new(&[InhibitType::Sleep, InhibitType::Idle]),
+        who: "player".to_string(),
+        why: "Playing music".to_string(),
+        mode: Mode::Block,
+        uid: 1000,
+        pid: 4242,
+    }
+}
+
+#[test]
+fn scripted_inhibitors() {
+    future::block_on(async {
+        let fake = FakeLogind::new().await.unwrap();
+        let manager = ManagerProxy::new(fake.connection()).await.unwrap();
+        fake.add_inhibitor(player()).await.unwrap();
+
+        assert_eq!(manager.list_inhibitors().await.unwrap().len(), 1);
+        assert_eq!(manager.NCurrent_inhibitors().await.unwrap(), 1);
+        assert_eq!(
+            manager.block_inhibited().await.unwrap(),
+            InhibitType::Sleep | InhibitType::Idle
+        );
+        assert!(manager.delay_inhibited().await.unwrap().is_empty());
+
+        fake.clear_inhibitors().await.unwrap();
+        assert!(manager.list_inhibitors().await.unwrap().is_empty());
+    })
+}
@@ -0,0 +1,142 @@
+//! `org.freedesktop.login1.User` as served by [`super::FakeLogind`]
+
+use std::sync::Arc;
+
+use zbus::{dbus_interface, fdo, zvariant::OwnedObjectPath, Connection};
+
+use super::{root_path, session_path, user_path, user_state, value, Error, FakeUser, Shared};
+use crate::session::SessionType;
+
+pub(super) struct UserIface {
+    shared: Arc<Shared>,
+    uid: u32,
+}
+
+impl UserIface {
+    pub(super) fn new(shared: Arc<Shared>, uid: u32) -> Self {
+        Self { shared, uid }
+    }
+
+    fn get<T>(&self, f: impl FnOnce(&FakeUser) -> T) -> fdo::Result<T> {
+        let state = self.shared.state();
+        let user = state
+            .users
+            .get(&self.uid)
+            .ok_or_else(|| fdo::Error::UnknownObject(format!("No user {}", self.uid)))?;
+        Ok(f(user))
+    }
+}
+
+#[dbus_interface(name = "org.freedesktop.login1.User")]
+impl UserIface {
+    fn kill(&self, signal_number: i32) {
+        self.shared
+            .state()
+            .record(&user_path(self.uid), "Kill", vec![value(signal_number)]);
+    }
+
+    async fn terminate(&self, #[zbus(connection)] conn: &Connection) -> Result<(), Error> {
+        let uid = self.uid;
+        Shared::update(&self.shared, conn, |state| {
+            state.sessions.retain(|_, s| s.uid != uid);
+            if !state.users.get(&uid).is_some_and(|u| u.linger) {
+                state.users.remove(&uid);
+            }
+        })
+        .await?;
+        Ok(())
+    }
+
+    /// The first graphical session of the user, falling back to any session
+    #[dbus_interface(property)]
+    fn display(&self) -> (String, OwnedObjectPath) {
+        let state = self.shared.state();
+        let mut sessions = state.sessions.values().filter(|s| s.uid == self.uid);
+        let graphical = sessions.clone().find(|s| {
+            matches!(
+                s.type_,
+                SessionType::X11 | SessionType::Wayland | SessionType::MIR
+            )
+        });
+        graphical
+            .or_else(|| sessions.next())
+            .map(|s| (s.id.clone(), session_path(&s.id)))
+            .unwrap_or_else(|| (String::new(), root_path()))
+    }
+
+    #[dbus_interface(property, name = "GID")]
+    fn gid(&self) -> fdo::Result<u32> {
+        self.get(|u| u.gid)
+    }
+
+    #[dbus_interface(property)]
+    fn idle_hint(&self) -> fdo::Result<bool> {
+        self.get(|u| u.idle_hint)
+    }
+
+    #[dbus_interface(property)]
+    fn idle_since_hint(&self) -> fdo::Result<u64> {
+        self.get(|u| u.idle_since_hint)
+    }
+
+    #[dbus_interface(property)]
+    fn idle_since_hint_monotonic(&self) -> fdo::Result<u64> {
+        self.get(|u| u.idle_since_hint_monotonic)
+    }
+
+    #[dbus_interface(property)]
+    fn linger(&self) -> fdo::Result<bool> {
+        self.get(|u| u.linger)
+    }
+
+    #[dbus_interface(property)]
+    fn name(&self) -> fdo::Result<String> {
+        self.get(|u| u.name.clone())
+    }
+
+    #[dbus_interface(property)]
+    fn runtime_path(&self) -> fdo::Result<String> {
+        self.get(|u| u.runtime_path.clone())
+    }
+
+    #[dbus_interface(property)]
+    fn service(&self) -> fdo::Result<String> {
+        self.get(|u| u.service.clone())
+    }
+
+    #[dbus_interface(property)]
+    fn sessions(&self) -> Vec<(String, OwnedObjectPath)> {
+        self.shared
+            .state()
+            .sessions
+            .values()
+            .filter(|s| s.uid == self.uid)
+            .map(|s| (s.id.clone(), session_path(&s.id)))
+            .collect()
+    }
+
+    #[dbus_interface(property)]
+    fn slice(&self) -> fdo::Result<String> {
+        self.get(|u| u.slice.clone())
+    }
+
+    #[dbus_interface(property)]
+    fn state(&self) -> String {
+        user_state(&self.shared.state(), self.uid).to_string()
+    }
+
+    #[dbus_interface(property)]
+    fn timestamp(&self) -> fdo::Result<u64> {
+        self.get(|u| u.timestamp)
+    }
+
+    #[dbus_interface(property)]
+    fn timestamp_monotonic(&self) -> fdo::Result<u64> {
+        self.get(|u| u.timestamp_monotonic)
+    }
+
+    #[dbus_interface(property, name = "UID")]
+    fn uid(&self) -> u32 {
+        self.uid
+    }
+}
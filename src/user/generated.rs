@@ -4,7 +4,7 @@
 
 use zbus::dbus_proxy;
 
-use crate::{SomePath, TimeStamp};
+use crate::{MonotonicStamp, RealtimeStamp, SomePath};
 
 use super::UserState;
 
@@ -40,12 +40,12 @@ trait User {
     /// IdleSinceHint property
     #[dbus_proxy(property)]
     #[inline]
-    fn idle_since_hint(&self) -> zbus::Result<TimeStamp>;
+    fn idle_since_hint(&self) -> zbus::Result<RealtimeStamp>;
 
     /// IdleSinceHintMonotonic property
     #[dbus_proxy(property)]
     #[inline]
-    fn idle_since_hint_monotonic(&self) -> zbus::Result<TimeStamp>;
+    fn idle_since_hint_monotonic(&self) -> zbus::Result<MonotonicStamp>;
 
     /// Linger property
     #[dbus_proxy(property)]
@@ -85,12 +85,12 @@ trait User {
     /// Timestamp property
     #[dbus_proxy(property)]
     #[inline]
-    fn timestamp(&self) -> zbus::Result<TimeStamp>;
+    fn timestamp(&self) -> zbus::Result<RealtimeStamp>;
 
     /// TimestampMonotonic property
     #[dbus_proxy(property)]
     #[inline]
-    fn timestamp_monotonic(&self) -> zbus::Result<TimeStamp>;
+    fn timestamp_monotonic(&self) -> zbus::Result<MonotonicStamp>;
 
     /// UID property
     #[dbus_proxy(property)]
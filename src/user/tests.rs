@@ -1,13 +1,16 @@
 use crate::manager::ManagerProxy;
 use crate::manager::ManagerProxyBlocking;
+use crate::testing;
 use crate::user::UserProxy;
 use crate::user::UserProxyBlocking;
 use crate::IntoPath;
 use futures_lite::future;
+use std::time::{Instant, SystemTime};
 
 #[test]
 fn timestamps() {
-    let connection = zbus::blocking::Connection::system().unwrap();
+    let fake = future::block_on(testing::desktop());
+    let connection = fake.blocking_connection();
     let manager = ManagerProxyBlocking::new(&connection).unwrap();
     let users = manager.list_users().unwrap();
     let user = UserProxyBlocking::builder(&connection)
@@ -17,15 +20,16 @@ fn timestamps() {
         .unwrap();
 
     let time1 = user.timestamp().unwrap();
-    assert!(time1.as_secs() > 0);
+    assert!(time1.to_system_time().unwrap() <= SystemTime::now());
 
     let time2 = user.timestamp_monotonic().unwrap();
-    assert!(time2.as_secs() > 0);
+    assert!(time2.to_instant().unwrap() <= Instant::now());
 }
 
 #[test]
 fn properties() {
-    let connection = zbus::blocking::Connection::system().unwrap();
+    let fake = future::block_on(testing::desktop());
+    let connection = fake.blocking_connection();
     let manager = ManagerProxyBlocking::new(&connection).unwrap();
     let users = manager.list_users().unwrap();
     let user = UserProxyBlocking::builder(&connection)
@@ -56,7 +60,8 @@ fn properties() {
 #[test]
 fn timestamps_async() {
     future::block_on(async {
-        let connection = zbus::Connection::system().await.unwrap();
+        let fake = testing::desktop().await;
+        let connection = fake.connection().clone();
         let manager = ManagerProxy::new(&connection).await.unwrap();
         let users = manager.list_users().await.unwrap();
         let user = UserProxy::builder(&connection)
@@ -67,17 +72,18 @@ fn timestamps_async() {
             .unwrap();
 
         let time1 = user.timestamp().await.unwrap();
-        assert!(time1.as_secs() > 0);
+        assert!(time1.to_system_time().unwrap() <= SystemTime::now());
 
         let time2 = user.timestamp_monotonic().await.unwrap();
-        assert!(time2.as_secs() > 0);
+        assert!(time2.to_instant().unwrap() <= Instant::now());
     });
 }
 
 #[test]
 fn properties_async() {
     future::block_on(async {
-        let connection = zbus::Connection::system().await.unwrap();
+        let fake = testing::desktop().await;
+        let connection = fake.connection().clone();
         let manager = ManagerProxy::new(&connection).await.unwrap();
         let users = manager.list_users().await.unwrap();
         let user = UserProxy::builder(&connection)
@@ -1,11 +1,10 @@
 use crate::{enum_impl_serde_str, enum_impl_str_conv, impl_try_from_owned_as_str};
 use serde::{Deserialize, Serialize};
 use std::str::FromStr;
-use zbus::fdo;
 use zbus::zvariant::{OwnedValue, Type};
 
 /// State of a User
-#[derive(Debug, PartialEq, Eq, Clone, Copy, Type)]
+#[derive(Debug, PartialEq, Eq, Clone, Type)]
 #[zvariant(signature = "s")]
 pub enum UserState {
     Online,
@@ -13,6 +12,8 @@ pub enum UserState {
     Lingering,
     Active,
     Closing,
+    /// A value not known to this crate, as sent by logind
+    Other(String),
 }
 enum_impl_serde_str!(UserState);
 impl_try_from_owned_as_str!(UserState);